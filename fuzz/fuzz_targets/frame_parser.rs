@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mhz19_rs::mhz19::{FrameParser, Reading, START_BYTE};
+
+// Arbitrary byte stream must never panic the parser, and every emitted
+// frame must start with single start byte and have valid checksum
+fuzz_target!(|data: &[u8]| {
+    let mut parser = FrameParser::new(START_BYTE);
+
+    for &byte in data {
+        if let Some(frame) = parser.push(byte) {
+            assert_eq!(frame[0], START_BYTE);
+            assert_ne!(frame[1], START_BYTE);
+            assert!(Reading::decode(frame).is_ok());
+        }
+    }
+});
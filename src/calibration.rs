@@ -0,0 +1,95 @@
+use crate::mhz19::{Errors, Mhz19Trait};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    Dwelling,
+    Ready
+}
+
+///
+/// Guarded zero point calibration. Datasheet requires sensor to stay in stable
+/// 400 ppm environment for 20 minutes before zero calibration, so the command
+/// is sent by `commit()` only after that time passed since `start()`.
+///
+/// # Example
+///
+/// ```
+/// let mut calibration = ZeroCalibration::new();
+///
+/// calibration.start();
+///
+/// loop {
+///     if calibration.tick(1000) {
+///         calibration.commit(&mut mhz).unwrap();
+///         break;
+///     }
+///
+///     delay.delay_ms(1000_u16);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ZeroCalibration {
+    state: State,
+    elapsed_ms: u32
+}
+
+impl ZeroCalibration {
+    /// Required time in fresh air before zero calibration, ms
+    pub const DWELL_MS: u32 = 20 * 60 * 1000;
+
+    pub const fn new() -> Self {
+        Self {
+            state: State::Idle,
+            elapsed_ms: 0
+        }
+    }
+
+    /// Start dwell time, call when sensor is placed into fresh air
+    pub fn start(&mut self) {
+        self.state = State::Dwelling;
+        self.elapsed_ms = 0;
+    }
+
+    /// Account `elapsed_ms` passed since previous tick, returns `true` when ready to commit
+    pub fn tick(&mut self, elapsed_ms: u32) -> bool {
+        if self.state == State::Dwelling {
+            self.elapsed_ms = self.elapsed_ms.saturating_add(elapsed_ms);
+
+            if self.elapsed_ms >= Self::DWELL_MS {
+                self.state = State::Ready;
+            }
+        }
+
+        self.is_ready()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.state == State::Ready
+    }
+
+    ///
+    /// Send zero calibration command. Returns `Errors::NotReady` if dwell time
+    /// hasn't passed (or `start()` wasn't called). After success calibration must
+    /// be started again.
+    pub fn commit<Mhz19Type>(&mut self, mhz: &mut Mhz19Type) -> Result<(), Errors>
+        where
+            Mhz19Type: Mhz19Trait<Error = Errors>
+    {
+        if !self.is_ready() {
+            return Err(Errors::NotReady);
+        }
+
+        mhz.calibrate_zero()?;
+
+        self.state = State::Idle;
+
+        Ok(())
+    }
+}
+
+impl Default for ZeroCalibration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,151 @@
+use crate::mhz19::{AutoCalibrationState, Command, Mhz19Trait, Range, Reading};
+
+///
+/// Driver wrapper which keeps last `N` successful readings, e.g. for plotting.
+/// Every `measure()` (and `co2()`, which goes through it) is recorded, oldest
+/// reading is dropped when full. Readings are stored inline in a fixed array,
+/// so memory cost is `N * size_of::<Reading>()` on top of the driver, no allocator
+/// needed.
+///
+/// # Example
+///
+/// ```
+/// let mhz = Mhz19::new(serial);
+/// let mut mhz = History::<_, 60>::new(mhz);
+///
+/// mhz.measure().unwrap();
+///
+/// for reading in mhz.history() {
+///     // oldest first
+/// }
+/// ```
+pub struct History<Mhz19Type, const N: usize>
+    where
+        Mhz19Type: Mhz19Trait
+{
+    mhz: Mhz19Type,
+    readings: [Reading; N],
+    len: usize
+}
+
+impl<Mhz19Type, const N: usize> History<Mhz19Type, N>
+    where
+        Mhz19Type: Mhz19Trait
+{
+    pub fn new(mhz: Mhz19Type) -> Self {
+        let empty = Reading { co2: 0, temperature: None, status: 0, below_floor: false, saturated: false };
+
+        Self {
+            mhz,
+            readings: [empty; N],
+            len: 0
+        }
+    }
+
+    /// Recorded readings, oldest first
+    pub fn history(&self) -> &[Reading] {
+        &self.readings[..self.len]
+    }
+
+    ///
+    /// Median concentration of recorded readings, robust against single spikes the
+    /// sensor occasionally reports. Mean of two middle readings for even count, `None`
+    /// if nothing is recorded. Sorts a copy on stack (`N * 2` bytes), no allocation.
+    pub fn median(&self) -> Option<u16> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let mut co2: [u16; N] = [0; N];
+
+        for (value, reading) in co2.iter_mut().zip(self.history()) {
+            *value = reading.co2;
+        }
+
+        let sorted = &mut co2[..self.len];
+
+        sorted.sort_unstable();
+
+        let middle = self.len / 2;
+
+        if self.len % 2 == 1 {
+            Some(sorted[middle])
+        } else {
+            let (low, high) = (sorted[middle - 1] as u32, sorted[middle] as u32);
+
+            Some(((low + high) / 2) as u16)
+        }
+    }
+
+    /// Drop recorded readings
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Access wrapped driver, readings taken through it directly are not recorded
+    pub fn mhz(&mut self) -> &mut Mhz19Type {
+        &mut self.mhz
+    }
+
+    // Append reading, dropping the oldest one when full
+    fn push(&mut self, reading: Reading) {
+        if N == 0 {
+            return;
+        }
+
+        if self.len == N {
+            self.readings.rotate_left(1);
+            self.len -= 1;
+        }
+
+        self.readings[self.len] = reading;
+        self.len += 1;
+    }
+}
+
+impl<Mhz19Type, const N: usize> Mhz19Trait for History<Mhz19Type, N>
+    where
+        Mhz19Type: Mhz19Trait
+{
+    type Error = Mhz19Type::Error;
+
+    fn co2(&mut self) -> Result<u16, Self::Error> {
+        Ok(self.measure()?.co2)
+    }
+
+    fn measure(&mut self) -> Result<Reading, Self::Error> {
+        let reading = self.mhz.measure()?;
+
+        self.push(reading);
+
+        Ok(reading)
+    }
+
+    fn calibrate_zero(&mut self) -> Result<(), Self::Error> {
+        self.mhz.calibrate_zero()
+    }
+
+    fn calibrate_span(&mut self, ppm: u16) -> Result<(), Self::Error> {
+        self.mhz.calibrate_span(ppm)
+    }
+
+    fn auto_calibration(&mut self, state: AutoCalibrationState) -> Result<(), Self::Error> {
+        self.mhz.auto_calibration(state)
+    }
+
+    fn read_auto_calibration(&mut self) -> Result<AutoCalibrationState, Self::Error> {
+        self.mhz.read_auto_calibration()
+    }
+
+    fn range(&mut self, range: Range) -> Result<(), Self::Error> {
+        self.mhz.range(range)
+    }
+
+    fn read_range(&mut self) -> Result<Range, Self::Error> {
+        self.mhz.read_range()
+    }
+
+    fn supports(&self, cmd: Command) -> bool {
+        self.mhz.supports(cmd)
+    }
+}
@@ -1,15 +1,32 @@
 use embedded_hal::serial::{Write, Read};
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::timer::CountDown;
+use core::fmt;
+use heapless::consts;
+#[cfg(feature = "async")]
+use core::time::Duration;
+#[cfg(feature = "async")]
+use crate::stream::{AsyncTimer, ReadingStream};
 
-#[allow(dead_code)]
+/// Sensor command, value is command byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
-enum Commands {
+pub enum Command {
     ReadConcentration = 0x86,
+    ReadRawConcentration = 0x84,
     CalibrateZeroPoint = 0x87,
     CalibrateSpanPoint = 0x88,
     AutoCalibration = 0x79,
-    SetRange = 0x99
+    ReadAutoCalibration = 0x7D,
+    SetRange = 0x99,
+    ReadRange = 0x9B,
+    /// Reset settings to factory defaults, not in datasheet (community documented)
+    FactoryReset = 0x78,
+    /// Read firmware version, not in datasheet (community documented)
+    ReadFirmwareVersion = 0xA0
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Range {
     _1000,
     _2000,
@@ -18,263 +35,3909 @@ pub enum Range {
     _10000
 }
 
+impl Range {
+    /// Upper limit of range, ppm
+    pub const fn ppm(&self) -> u16 {
+        match self {
+            Range::_1000 => { 1000 }
+            Range::_2000 => { 2000 }
+            Range::_3000 => { 3000 }
+            Range::_5000 => { 5000 }
+            Range::_10000 => { 10000 }
+        }
+    }
+
+    // Data bytes of set range command, range is big-endian in bytes 3 and 4
+    const fn data(&self) -> [u8; 5] {
+        match self {
+            Range::_1000 => { [0x00, 0x00, 0x00, 0x03, 0xE8] }
+            Range::_2000 => { [0x00, 0x00, 0x00, 0x07, 0xD0] }
+            Range::_3000 => { [0x00, 0x00, 0x00, 0x0B, 0xB8] }
+            Range::_5000 => { [0x00, 0x00, 0x00, 0x13, 0x88] }
+            Range::_10000 => { [0x00, 0x00, 0x00, 0x27, 0x10] }
+        }
+    }
+
+    // Encoded data matches ppm value, checked at compile time below
+    const fn data_matches_ppm(&self) -> bool {
+        let data = self.data();
+
+        data[0] == 0
+            && data[1] == 0
+            && data[2] == 0
+            && u16::from_be_bytes([data[3], data[4]]) == self.ppm()
+    }
+}
+
+const _: () = assert!(Range::_1000.data_matches_ppm());
+const _: () = assert!(Range::_2000.data_matches_ppm());
+const _: () = assert!(Range::_3000.data_matches_ppm());
+const _: () = assert!(Range::_5000.data_matches_ppm());
+const _: () = assert!(Range::_10000.data_matches_ppm());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Errors {
     Write,
+    /// Read failed in custom `ReadFrame` implementation
     Read,
-    Checksum
+    Checksum,
+    NoSensor,
+    StartByte,
+    Timeout,
+    InvalidResponse,
+    Unsupported,
+    NotReady,
+    /// Response echoes other command than sent, e.g. sensor answers stale command
+    UnexpectedCommand {
+        expected: u8,
+        got: u8
+    },
+    /// Reading after calibration is not close to calibration value
+    CalibrationFailed,
+    /// Concentration above configured plausible maximum
+    ImplausibleValue,
+    /// Serial kept reporting `WouldBlock` over read poll limit, worth retrying
+    ReadWouldBlockTimeout,
+    /// Serial reported own error (`nb::Error::Other`), likely wiring or UART fault
+    ReadHardware,
+    /// Argument is out of accepted range or unusable with current configuration, e.g. alarm
+    /// threshold above full scale, address filter without address byte in layout
+    InvalidArgument,
+    /// Concentration is above full scale but plausible with swapped bytes, see
+    /// `Mhz19::set_byte_swap_check()`
+    PossibleByteSwap,
+    /// No frame with own address within scan limit of `Mhz19::set_address_filter()`
+    UnexpectedAddress
 }
 
+impl Errors {
+    ///
+    /// Stable numeric code of error for telemetry/FFI. Codes are never reused
+    /// or changed, new variants get new codes:
+    ///
+    /// | code | error                   |
+    /// |------|-------------------------|
+    /// | 1    | `Write`                 |
+    /// | 2    | `Read`                  |
+    /// | 3    | `Checksum`              |
+    /// | 4    | `NoSensor`              |
+    /// | 5    | `StartByte`             |
+    /// | 6    | `Timeout`               |
+    /// | 7    | `InvalidResponse`       |
+    /// | 8    | `Unsupported`           |
+    /// | 9    | `NotReady`              |
+    /// | 10   | `UnexpectedCommand`     |
+    /// | 11   | `CalibrationFailed`     |
+    /// | 12   | `ImplausibleValue`      |
+    /// | 13   | `ReadWouldBlockTimeout` |
+    /// | 14   | `ReadHardware`          |
+    /// | 15   | `InvalidArgument`       |
+    /// | 16   | `PossibleByteSwap`      |
+    /// | 17   | `UnexpectedAddress`     |
+    ///
+    /// Command bytes of `UnexpectedCommand` are not part of code, `from_code()`
+    /// returns them as zeroes.
+    pub fn code(&self) -> u8 {
+        match self {
+            Errors::Write => { 1 }
+            Errors::Read => { 2 }
+            Errors::Checksum => { 3 }
+            Errors::NoSensor => { 4 }
+            Errors::StartByte => { 5 }
+            Errors::Timeout => { 6 }
+            Errors::InvalidResponse => { 7 }
+            Errors::Unsupported => { 8 }
+            Errors::NotReady => { 9 }
+            Errors::UnexpectedCommand { .. } => { 10 }
+            Errors::CalibrationFailed => { 11 }
+            Errors::ImplausibleValue => { 12 }
+            Errors::ReadWouldBlockTimeout => { 13 }
+            Errors::ReadHardware => { 14 }
+            Errors::InvalidArgument => { 15 }
+            Errors::PossibleByteSwap => { 16 }
+            Errors::UnexpectedAddress => { 17 }
+        }
+    }
+
+    /// Error from code returned by `code()`, `None` for unknown code
+    pub fn from_code(code: u8) -> Option<Errors> {
+        match code {
+            1 => { Some(Errors::Write) }
+            2 => { Some(Errors::Read) }
+            3 => { Some(Errors::Checksum) }
+            4 => { Some(Errors::NoSensor) }
+            5 => { Some(Errors::StartByte) }
+            6 => { Some(Errors::Timeout) }
+            7 => { Some(Errors::InvalidResponse) }
+            8 => { Some(Errors::Unsupported) }
+            9 => { Some(Errors::NotReady) }
+            10 => { Some(Errors::UnexpectedCommand { expected: 0, got: 0 }) }
+            11 => { Some(Errors::CalibrationFailed) }
+            12 => { Some(Errors::ImplausibleValue) }
+            13 => { Some(Errors::ReadWouldBlockTimeout) }
+            14 => { Some(Errors::ReadHardware) }
+            15 => { Some(Errors::InvalidArgument) }
+            16 => { Some(Errors::PossibleByteSwap) }
+            17 => { Some(Errors::UnexpectedAddress) }
+            _ => { None }
+        }
+    }
+
+    ///
+    /// Whether error is transient and command is worth retrying: read timeouts,
+    /// corrupted or out of sync frames. Hardware errors and unsupported commands
+    /// fail the same way again.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Errors::ReadWouldBlockTimeout
+                | Errors::Timeout
+                | Errors::Checksum
+                | Errors::StartByte
+                | Errors::UnexpectedCommand { .. }
+                | Errors::UnexpectedAddress
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AutoCalibrationState {
     Enable,
     Disable
 }
 
-// Calculate checksum for mhz19 packet
-fn checksum(data: &[u8]) -> u8 {
-    let mut result: u8 = 0;
+/// State of zero/span calibration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalStatus {
+    Idle,
+    InProgress,
+    Done,
+    Failed
+}
 
-    for &number in data {
-        match result.overflowing_add(number) {
-            (value, _) => { result = value; }
+/// Measurement mode of sensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeasurementMode {
+    /// Sensor measures all the time, heater is always on
+    Continuous,
+    /// Sensor measures only when triggered, to save power
+    SingleShot
+}
+
+/// UART baud rate of sensor, see `Mhz19::set_baud()`
+#[cfg(feature = "experimental")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorBaud {
+    _2400,
+    _4800,
+    _9600,
+    _19200
+}
+
+#[cfg(feature = "experimental")]
+impl SensorBaud {
+    /// Rate in bits per second
+    pub const fn bps(&self) -> u32 {
+        match self {
+            SensorBaud::_2400 => { 2400 }
+            SensorBaud::_4800 => { 4800 }
+            SensorBaud::_9600 => { 9600 }
+            SensorBaud::_19200 => { 19200 }
         }
     }
+}
+
+/// Result of link diagnostics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diagnosis {
+    /// All pings succeeded
+    Ok,
+    /// Nothing received
+    NoResponse,
+    /// Bytes received but start byte never seen, serial baudrate is probably not 9600
+    BaudMismatch,
+    /// Frames received but some are corrupted
+    Noisy
+}
+
+/// Sensor settings applied in one call with `apply_config()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorConfig {
+    /// Detection range
+    pub range: Range,
+    /// Automatic baseline calibration
+    pub abc: AutoCalibrationState,
+    /// Measurement mode, `None` keeps current one
+    pub measurement_mode: Option<MeasurementMode>
+}
 
-    match 255_u8.overflowing_sub(result) {
-        (value, _) => { result = value; }
+impl Default for SensorConfig {
+    /// Factory state: 0..5000 ppm range, ABC enabled, continuous measurement
+    fn default() -> Self {
+        Self {
+            range: Range::_5000,
+            abc: AutoCalibrationState::Enable,
+            measurement_mode: Some(MeasurementMode::Continuous)
+        }
     }
+}
 
-    result
+/// Snapshot of all readable sensor parameters, see `Mhz19::info()`.
+/// Queries the model doesn't support are `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorInfo {
+    /// Firmware version as 4 ASCII characters, e.g. `b"0443"`
+    pub firmware_version: Option<[u8; 4]>,
+    /// Configured detection range
+    pub range: Option<Range>,
+    /// Automatic baseline calibration state
+    pub abc: Option<AutoCalibrationState>,
+    /// Current concentration and temperature
+    pub reading: Reading
 }
 
-/// Trait for implement mhz-19 logic
-pub trait Mhz19Trait {
-    type Error;
+/// Sensor model, some behavior (warm-up time etc.) depends on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Model {
+    /// MH-Z19B
+    #[default]
+    B,
+    /// MH-Z19C
+    C
+}
 
-    fn co2(&mut self) -> Result<u16, Self::Error>;
-    fn auto_calibration(&mut self, state: AutoCalibrationState) -> Result<(), Self::Error>;
-    fn range(&mut self, range: Range) -> Result<(), Self::Error>;
+/// Commands documented for MH-Z19B
+const MODEL_B_COMMANDS: [Command; 10] = [
+    Command::ReadConcentration,
+    Command::ReadRawConcentration,
+    Command::CalibrateZeroPoint,
+    Command::CalibrateSpanPoint,
+    Command::AutoCalibration,
+    Command::ReadAutoCalibration,
+    Command::SetRange,
+    Command::ReadRange,
+    Command::FactoryReset,
+    Command::ReadFirmwareVersion
+];
+
+/// Commands documented for MH-Z19C, it has no read back and raw commands
+const MODEL_C_COMMANDS: [Command; 5] = [
+    Command::ReadConcentration,
+    Command::CalibrateZeroPoint,
+    Command::CalibrateSpanPoint,
+    Command::AutoCalibration,
+    Command::SetRange
+];
+
+/// Values MH-Z19B firmware answers with while warming up
+const MODEL_B_WARMUP_SENTINELS: [u16; 2] = [0, 410];
+
+/// Values MH-Z19C firmware answers with while warming up
+const MODEL_C_WARMUP_SENTINELS: [u16; 2] = [0, 500];
+
+impl Model {
+    /// Commands supported by model
+    pub const fn commands(&self) -> &'static [Command] {
+        match self {
+            Model::B => { &MODEL_B_COMMANDS }
+            Model::C => { &MODEL_C_COMMANDS }
+        }
+    }
+
+    /// Nominal warm-up (preheat) time after power-on, ms
+    pub const fn warmup_ms(&self) -> u32 {
+        match self {
+            Model::B => { 180_000 }
+            Model::C => { 60_000 }
+        }
+    }
+
+    ///
+    /// Concentration values firmware reports instead of measurement while warming up:
+    /// zero for all models, 410 ppm for MH-Z19B and 500 ppm for MH-Z19C. A real
+    /// reading can hit these values too, so they only mean warm-up inside of
+    /// `warmup_ms()` after power-on.
+    pub const fn warmup_sentinels(&self) -> &'static [u16] {
+        match self {
+            Model::B => { &MODEL_B_WARMUP_SENTINELS }
+            Model::C => { &MODEL_C_WARMUP_SENTINELS }
+        }
+    }
+
+    /// `ppm` is one of warm-up sentinel values of model
+    pub fn is_warmup_value(&self, ppm: u16) -> bool {
+        self.warmup_sentinels().contains(&ppm)
+    }
 }
 
-const BUFFER_SIZE: usize = 9;
+/// Result of read concentration command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reading {
+    /// Gas concentration, ppm
+    pub co2: u16,
+    /// Sensor temperature, °C (rough, for compensation only).
+    /// `None` if decoded value is outside of plausible -40..=125 °C
+    pub temperature: Option<i16>,
+    /// Raw status byte, meaning depends on firmware
+    pub status: u8,
+    /// Concentration is below configured floor (400 ppm outdoor baseline by default),
+    /// probably sensor is warming up
+    pub below_floor: bool,
+    /// Concentration is at full scale of configured range, real one may be higher
+    pub saturated: bool
+}
 
 ///
-/// Mhz-19 implementation of the driver for transmission for serial.
-/// Need set serial baudrate 9600
+/// Bits of status byte (`Reading::status`). Meaning is not in datasheet and depends
+/// on model and firmware: masks below are observed on MH-Z19B, other firmware may
+/// use other bits or none. Unknown bits are ignored by accessors, `contains()` can
+/// test own masks.
 ///
 /// # Example
 ///
 /// ```
-/// let serial = Serial::new(...);
-/// let mut mhz = Mhz19::new(serial);
+/// let reading = mhz.measure().unwrap();
 ///
-/// let co2: u16 = mhz.co2().unwrap();
+/// if reading.flags().has_error() {
+///     // don't trust value
+/// }
 /// ```
-pub struct Mhz19<SerialType>
-    where
-        SerialType: Read<u8> + Write<u8>
-{
-    serial: SerialType,
-    buffer: [u8; BUFFER_SIZE]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StatusFlags {
+    bits: u8
 }
 
+impl StatusFlags {
+    /// Measurement is stable (warm-up done), MH-Z19B
+    pub const STABLE: u8 = 0x40;
+    /// Sensor reports internal error, MH-Z19B
+    pub const ERROR: u8 = 0x01;
 
-impl<SerialType> Mhz19<SerialType>
-    where
-        SerialType: Read<u8> + Write<u8>
-{
-    pub fn new(serial: SerialType) -> Self {
-        Self {
-            serial,
-            buffer: [0; BUFFER_SIZE]
-        }
+    pub const fn from_byte(byte: u8) -> Self {
+        Self { bits: byte }
     }
 
-    /// Send command to mhz-19 over serial
-    fn command(&mut self, cmd: u8, data: [u8; 5]) -> Result<(), Errors> {
-        self.buffer = [
-            0xFF,
-            0x01,
-            cmd,
-            data[0],
-            data[1],
-            data[2],
-            data[3],
-            data[4],
-            0x00
-        ];
-
-        let crc_index = BUFFER_SIZE - 1;
-
-        self.buffer[crc_index] = checksum(&self.buffer[0..crc_index]);
+    /// Raw status byte
+    pub const fn bits(&self) -> u8 {
+        self.bits
+    }
 
-        for &b in self.buffer.iter() {
-            match nb::block!(self.serial.write(b)) {
-                Err(_) => { return Err(Errors::Write); }
-                _ => {}
-            }
-        }
+    /// All bits of `mask` are set
+    pub const fn contains(&self, mask: u8) -> bool {
+        self.bits & mask == mask
+    }
 
-        Ok(())
+    pub const fn is_stable(&self) -> bool {
+        self.contains(Self::STABLE)
     }
 
-    /// Read response from mhz-19 driver with check checksum
-    fn response(&mut self) -> Result<(), Errors> {
-        for index in 0..BUFFER_SIZE {
-            match nb::block!(self.serial.read()) {
-                Ok(data) => { self.buffer[index] = data; }
-                Err(_) => { return Err(Errors::Read); }
-            }
-        }
+    pub const fn has_error(&self) -> bool {
+        self.contains(Self::ERROR)
+    }
+}
 
-        let crc_index = BUFFER_SIZE - 1;
+// Known whole status bytes, community findings on MH-Z19B
+const STATUS_DESCRIPTIONS: [(u8, &str); 3] = [
+    (0x00, "warming up"),
+    (StatusFlags::STABLE, "normal"),
+    (StatusFlags::ERROR, "error")
+];
 
-        if checksum(&self.buffer[0..crc_index]) != self.buffer[crc_index] {
-            return Err(Errors::Checksum);
+///
+/// Short human readable description of status byte (`Reading::status`) for
+/// displays and logs: "warming up", "normal", "error" or "unknown". Mapping is
+/// model dependent and based on community findings on MH-Z19B, not datasheet.
+///
+/// # Example
+///
+/// ```
+/// let reading = mhz.measure().unwrap();
+///
+/// writeln!(display, "{}", status_description(reading.status));
+/// ```
+pub fn status_description(status: u8) -> &'static str {
+    for &(byte, description) in STATUS_DESCRIPTIONS.iter() {
+        if byte == status {
+            return description;
         }
-
-        Ok(())
     }
+
+    "unknown"
 }
 
-impl<SerialType> Mhz19Trait for Mhz19<SerialType>
-    where
-        SerialType: Read<u8> + Write<u8>
-{
-    type Error = Errors;
+/// Start byte of MH-Z19 command and response frames
+pub const START_BYTE: u8 = 0xFF;
+/// MH-Z19 sensor address, second byte of command frame
+pub const SENSOR_ADDR: u8 = 0x01;
+/// Length of command and response frames
+pub const FRAME_LEN: usize = 9;
+/// Maximum number of frames answering one command, see `Mhz19::raw_transact_multi()`
+pub const MAX_RESPONSE_FRAMES: usize = 4;
+///
+/// Longest burst sensor sends in answer to one command, bytes: what UART RX, DMA
+/// or ring buffer must hold to avoid overruns. All documented commands answer with
+/// one frame (9 bytes), multi-frame queries of `raw_transact_multi()` with up to
+/// `MAX_RESPONSE_FRAMES`. Doesn't depend on features. Stray zero flood after
+/// power-on (see `Mhz19::set_startup_grace()`) is not bounded by it.
+pub const MAX_RESPONSE_LEN: usize = MAX_RESPONSE_FRAMES * FRAME_LEN;
 
-    /// Get gas concentration from mhz-19
-    fn co2(&mut self) -> Result<u16, Self::Error> {
-        let data: [u8; 5] = [0; 5];
+/// Framing bytes of the protocol. Some rebadged modules use a different
+/// start byte or address than MH-Z19.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Protocol {
+    /// First byte of command and response frames
+    pub start_byte: u8,
+    /// Sensor address, second byte of command frame
+    pub address: u8
+}
 
-        self.command(Commands::ReadConcentration as u8, data)?;
-        self.response()?;
+impl Protocol {
+    /// MH-Z19 framing: start byte `0xFF`, address `0x01`
+    pub const MHZ19: Protocol = Protocol { start_byte: START_BYTE, address: SENSOR_ADDR };
+}
 
-        let result: u16 = ((self.buffer[2] as u16) << 8_u16) | (self.buffer[3] as u16);
+impl Default for Protocol {
+    fn default() -> Self {
+        Protocol::MHZ19
+    }
+}
 
-        Ok(result)
+// Calculate checksum for mhz19 packet
+fn checksum(data: &[u8]) -> u8 {
+    let mut result: u8 = 0;
+
+    for &number in data {
+        result = result.wrapping_add(number);
     }
 
-    ///
-    /// Set auto calibration or not for mhz-19 driver
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// let serial = Serial::new(...);
-    /// let mut mhz = Mhz19::new(serial);
-    ///
-    /// mhz.auto_calibration(AutoCalibrationState::Disable).unwrap();
-    /// ```
-    ///
-    fn auto_calibration(&mut self, state: AutoCalibrationState) -> Result<(), Self::Error> {
-        let state_byte: u8 = match state {
-            AutoCalibrationState::Enable => { 0xA0 }
-            AutoCalibrationState::Disable => { 0x00 }
-        };
+    255_u8.wrapping_sub(result)
+}
 
-        let data: [u8; 5] = [state_byte, 0, 0, 0, 0];
+///
+/// Checksum algorithm of frames. Datasheet algorithm is [`StandardChecksum`], some
+/// clone modules use a different one (e.g. [`XorChecksum`]).
+///
+/// # Example
+///
+/// ```
+/// struct CloneChecksum;
+///
+/// impl Checksum for CloneChecksum {
+///     fn checksum(&self, data: &[u8]) -> u8 { ... }
+/// }
+///
+/// let mut mhz = Mhz19::builder(serial).checksum(&CloneChecksum).build();
+/// ```
+pub trait Checksum {
+    /// Calculate checksum of first 8 bytes of frame (`data`)
+    fn checksum(&self, data: &[u8]) -> u8;
+}
 
-        self.command(Commands::AutoCalibration as u8, data)
+/// MH-Z19 datasheet checksum: negated sum of bytes 1..7
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StandardChecksum;
+
+impl Checksum for StandardChecksum {
+    fn checksum(&self, data: &[u8]) -> u8 {
+        checksum(data)
     }
+}
 
-    ///
-    /// Set maximum range for mhz-19 conversation (from 0 to range value)
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// let serial = Serial::new(...);
-    /// let mut mhz = Mhz19::new(serial);
-    ///
-    /// mhz.range(Range::_2000).unwrap();
-    /// ```
-    fn range(&mut self, range: Range) -> Result<(), Self::Error> {
-        let data: [u8; 5] = match range {
-            Range::_1000 => { [0x00, 0x00, 0x00, 0x03, 0xE8] }
-            Range::_2000 => { [0x00, 0x00, 0x00, 0x07, 0xD0] }
-            Range::_3000 => { [0x00, 0x00, 0x00, 0x0B, 0xB8] }
-            Range::_5000 => { [0x00, 0x00, 0x00, 0x13, 0x88] }
-            Range::_10000 => { [0x00, 0x00, 0x00, 0x27, 0x10] }
-        };
+/// XOR of bytes 1..7, start byte is not included
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XorChecksum;
 
-        self.command(Commands::SetRange as u8, data)
+impl Checksum for XorChecksum {
+    fn checksum(&self, data: &[u8]) -> u8 {
+        data.iter().skip(1).fold(0, |result, &number| result ^ number)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use heapless::{Vec, consts};
+// Build command frame with checksum
+fn build_frame(
+    protocol: &Protocol,
+    checksum: &dyn Checksum,
+    cmd: u8,
+    data: [u8; 5]
+) -> [u8; BUFFER_SIZE] {
+    let mut frame: [u8; BUFFER_SIZE] = [
+        protocol.start_byte,
+        protocol.address,
+        cmd,
+        data[0],
+        data[1],
+        data[2],
+        data[3],
+        data[4],
+        0x00
+    ];
 
-    struct DummySerial<'a> {
-        input: &'a mut Vec<u8, consts::U9>,
-        output: &'a mut Vec<u8, consts::U9>
-    }
+    let crc_index = BUFFER_SIZE - 1;
 
-    impl<'a> DummySerial<'a> {
-        fn new(
-            input: &'a mut Vec<u8, consts::U9>,
-            output: &'a mut Vec<u8, consts::U9>
-        ) -> Self
-        {
-            Self {
-                input,
-                output
-            }
+    frame[crc_index] = checksum.checksum(&frame[0..crc_index]);
+
+    frame
+}
+
+///
+/// Write MH-Z19 command frame for `cmd` with `data` to `writer` as space separated
+/// hex bytes (`FF 01 86 00 00 00 00 00 79`), e.g. for logs or test vectors.
+/// Frame is the same as sent by [`Mhz19`] with default protocol and checksum.
+///
+/// # Example
+///
+/// ```
+/// let mut line: heapless::String<consts::U32> = heapless::String::new();
+///
+/// write_command_to(&mut line, Command::ReadConcentration as u8, [0; 5]).unwrap();
+/// rtt_log(&line);
+/// ```
+pub fn write_command_to<WriterType>(writer: &mut WriterType, cmd: u8, data: [u8; 5]) -> fmt::Result
+    where
+        WriterType: fmt::Write
+{
+    let frame = build_frame(&Protocol::MHZ19, &StandardChecksum, cmd, data);
+
+    for (index, byte) in frame.iter().enumerate() {
+        if index > 0 {
+            writer.write_char(' ')?;
         }
+
+        write!(writer, "{:02X}", byte)?;
     }
 
-    impl<'a> Write<u8> for DummySerial<'a> {
-        type Error = nb::Error<()>;
+    Ok(())
+}
 
-        fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
-            self.input.push(word).unwrap();
+///
+/// Byte by byte frame parser for interrupt driven reception. Bytes before start
+/// byte are dropped, repeated start bytes are collapsed, and only frames with
+/// valid checksum are returned.
+///
+/// # Example
+///
+/// ```
+/// let mut parser = FrameParser::new(START_BYTE);
+///
+/// // in RX interrupt
+/// if let Some(frame) = parser.push(byte) {
+///     let reading = Reading::decode(frame);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct FrameParser {
+    start_byte: u8,
+    checksum: &'static dyn Checksum,
+    frame: [u8; BUFFER_SIZE],
+    len: usize
+}
 
-            Ok(())
+impl fmt::Debug for FrameParser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameParser")
+            .field("start_byte", &self.start_byte)
+            .field("frame", &self.frame)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl FrameParser {
+    pub const fn new(start_byte: u8) -> Self {
+        Self::with_checksum(start_byte, &StandardChecksum)
+    }
+
+    /// Create parser for frames with non-standard checksum algorithm
+    pub const fn with_checksum(start_byte: u8, checksum: &'static dyn Checksum) -> Self {
+        Self {
+            start_byte,
+            checksum,
+            frame: [0; BUFFER_SIZE],
+            len: 0
         }
+    }
 
-        fn flush(&mut self) -> nb::Result<(), Self::Error> { Ok(()) }
+    /// Drop partially received frame
+    pub fn reset(&mut self) {
+        self.len = 0;
     }
 
-    impl<'a> Read<u8> for DummySerial<'a> {
-        type Error = nb::Error<()>;
+    /// Push received byte, returns frame when complete frame with valid checksum received
+    pub fn push(&mut self, byte: u8) -> Option<[u8; FRAME_LEN]> {
+        if self.len == 0 && byte != self.start_byte {
+            return None;
+        }
 
-        fn read(&mut self) -> nb::Result<u8, Self::Error> {
-            return match self.output.pop() {
-                Some(data) => { Ok(data) }
-                _ => { Err(nb::Error::WouldBlock) }
-            };
+        // stuffed start byte, frame starts from the last one
+        if self.len == 1 && byte == self.start_byte {
+            return None;
         }
-    }
 
-    #[test]
-    fn test_checksum() {
-        // example data from datasheet
-        let test_data_1: [u8; 9] = [0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79];
-        let test_data_2: [u8; 9] = [0xFF, 0x01, 0x87, 0x00, 0x00, 0x00, 0x00, 0x00, 0x78];
-        let test_data_3: [u8; 9] = [0xFF, 0x01, 0x88, 0x07, 0xD0, 0x00, 0x00, 0x00, 0xA0];
+        self.frame[self.len] = byte;
+        self.len += 1;
 
-        assert_eq!(checksum(&test_data_1[0..8]), test_data_1[8]);
-        assert_eq!(checksum(&test_data_2[0..8]), test_data_2[8]);
-        assert_eq!(checksum(&test_data_3[0..8]), test_data_3[8]);
-    }
+        if self.len < BUFFER_SIZE {
+            return None;
+        }
 
-    #[test]
-    fn command_test() {
-        let mut input: Vec<u8, consts::U9> = Vec::new();
-        let mut _output: Vec<u8, consts::U9> = Vec::new();
+        if frame_checksum_ok(self.checksum, &self.frame) {
+            self.len = 0;
+            return Some(self.frame);
+        }
 
-        let serial = DummySerial::new(&mut input, &mut _output);
+        // wrong frame, resync by pushing it again without its start byte; the rest
+        // is shorter than frame, so nothing can complete here
+        let rest = self.frame;
 
-        let mut mhz = Mhz19::new(serial);
+        self.len = 0;
 
-        match mhz.command(0x86, [0_u8; 5]) {
-            Ok(_) => {}
-            _ => { panic!("Error write command"); }
+        for &b in rest[1..].iter() {
+            self.push(b);
         }
 
-        let expected_answer: [u8; 9] = [
-            0xFF,
+        None
+    }
+}
+
+/// Serial which can fill a whole buffer in one call, e.g. UART with DMA.
+///
+/// Available with the `read-buf` feature. Wrap such serial in [`BufferedSerial`]
+/// to read response with a single `read_buf` call instead of nine `read` calls.
+#[cfg(feature = "read-buf")]
+pub trait ReadBuf {
+    type Error;
+
+    /// Fill all of `buffer` from serial
+    fn read_buf(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error>;
+}
+
+/// Read response bytes from serial.
+///
+/// Implemented for every `Read<u8>` (byte by byte) and for [`BufferedSerial`]
+/// (single `read_buf` call per frame). Other serials can implement it directly,
+/// reporting own failures as `Errors::Read`.
+pub trait ReadFrame {
+    /// Read single byte without blocking
+    fn read_byte(&mut self) -> nb::Result<u8, Errors>;
+
+    fn read_frame(&mut self, buffer: &mut [u8]) -> Result<(), Errors> {
+        self.read_frame_polled(buffer, &mut 0, None)
+    }
+
+    ///
+    /// Same as `read_frame()`, adding number of `WouldBlock` polls to `polls`.
+    /// Gives up with `Errors::ReadWouldBlockTimeout` once `polls` exceeds
+    /// `max_polls`, `None` waits forever.
+    fn read_frame_polled(
+        &mut self,
+        buffer: &mut [u8],
+        polls: &mut u32,
+        max_polls: Option<u32>
+    ) -> Result<(), Errors> {
+        for data in buffer.iter_mut() {
+            loop {
+                match self.read_byte() {
+                    Ok(byte) => {
+                        *data = byte;
+                        break;
+                    }
+                    Err(nb::Error::WouldBlock) => { count_poll(polls, max_polls)?; }
+                    Err(nb::Error::Other(error)) => { return Err(error); }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<SerialType> ReadFrame for SerialType
+    where
+        SerialType: Read<u8>
+{
+    fn read_byte(&mut self) -> nb::Result<u8, Errors> {
+        self.read().map_err(|error| error.map(|_| Errors::ReadHardware))
+    }
+}
+
+///
+/// Adapter reading whole response frame with one [`ReadBuf::read_buf`] call, e.g.
+/// from DMA. Other serials are read byte by byte, so this is opt-in per serial.
+///
+/// Available with the `read-buf` feature.
+///
+/// # Example
+///
+/// ```
+/// let serial = DmaSerial::new(...);
+/// let mut mhz = Mhz19::new(BufferedSerial::new(serial));
+///
+/// let co2: u16 = mhz.co2().unwrap();
+/// ```
+#[cfg(feature = "read-buf")]
+pub struct BufferedSerial<SerialType> {
+    serial: SerialType
+}
+
+#[cfg(feature = "read-buf")]
+impl<SerialType> BufferedSerial<SerialType>
+    where
+        SerialType: ReadBuf
+{
+    pub const fn new(serial: SerialType) -> Self {
+        Self { serial }
+    }
+
+    /// Release wrapped serial
+    pub fn free(self) -> SerialType {
+        self.serial
+    }
+}
+
+#[cfg(feature = "read-buf")]
+impl<SerialType> Write<u8> for BufferedSerial<SerialType>
+    where
+        SerialType: Write<u8>
+{
+    type Error = SerialType::Error;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.serial.write(word)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.serial.flush()
+    }
+}
+
+#[cfg(feature = "read-buf")]
+impl<SerialType> ReadFrame for BufferedSerial<SerialType>
+    where
+        SerialType: ReadBuf
+{
+    fn read_byte(&mut self) -> nb::Result<u8, Errors> {
+        let mut byte: [u8; 1] = [0];
+
+        match self.serial.read_buf(&mut byte) {
+            Ok(_) => { Ok(byte[0]) }
+            Err(nb::Error::WouldBlock) => { Err(nb::Error::WouldBlock) }
+            Err(nb::Error::Other(_)) => { Err(nb::Error::Other(Errors::ReadHardware)) }
+        }
+    }
+
+    fn read_frame_polled(
+        &mut self,
+        buffer: &mut [u8],
+        polls: &mut u32,
+        max_polls: Option<u32>
+    ) -> Result<(), Errors> {
+        loop {
+            match self.serial.read_buf(buffer) {
+                Ok(_) => { return Ok(()); }
+                Err(nb::Error::WouldBlock) => { count_poll(polls, max_polls)?; }
+                Err(nb::Error::Other(_)) => { return Err(Errors::ReadHardware); }
+            }
+        }
+    }
+}
+
+// Count `WouldBlock` poll, failing once over limit
+fn count_poll(polls: &mut u32, max_polls: Option<u32>) -> Result<(), Errors> {
+    *polls = polls.saturating_add(1);
+
+    match max_polls {
+        Some(max_polls) if *polls > max_polls => { Err(Errors::ReadWouldBlockTimeout) }
+        _ => { Ok(()) }
+    }
+}
+
+///
+/// Transport which moves whole frames, e.g. USB HID report or network packet per
+/// frame. Wrap it in [`TransportSerial`] to use it with [`Mhz19`].
+pub trait FrameTransport {
+    /// Send command frame
+    fn send_frame(&mut self, frame: &[u8; FRAME_LEN]) -> nb::Result<(), ()>;
+
+    /// Receive response frame, `WouldBlock` until complete frame arrived
+    fn recv_frame(&mut self) -> nb::Result<[u8; FRAME_LEN], ()>;
+}
+
+///
+/// Adapter of [`FrameTransport`] to serial traits of [`Mhz19`]: written bytes are
+/// collected and sent as one frame, received frame is handed out byte by byte. All
+/// driver checks (start byte, checksum, echo etc.) apply as with UART.
+///
+/// # Example
+///
+/// ```
+/// let hid = HidTransport::new(...);
+/// let mut mhz = Mhz19::new(TransportSerial::new(hid));
+///
+/// let co2: u16 = mhz.co2().unwrap();
+/// ```
+pub struct TransportSerial<TransportType> {
+    transport: TransportType,
+    tx_frame: [u8; FRAME_LEN],
+    tx_len: usize,
+    rx_frame: [u8; FRAME_LEN],
+    rx_index: usize
+}
+
+impl<TransportType> TransportSerial<TransportType>
+    where
+        TransportType: FrameTransport
+{
+    pub const fn new(transport: TransportType) -> Self {
+        Self {
+            transport,
+            tx_frame: [0; FRAME_LEN],
+            tx_len: 0,
+            rx_frame: [0; FRAME_LEN],
+            rx_index: FRAME_LEN
+        }
+    }
+
+    /// Release wrapped transport
+    pub fn free(self) -> TransportType {
+        self.transport
+    }
+}
+
+impl<TransportType> Write<u8> for TransportSerial<TransportType>
+    where
+        TransportType: FrameTransport
+{
+    type Error = ();
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.tx_frame[self.tx_len] = word;
+
+        if self.tx_len + 1 < FRAME_LEN {
+            self.tx_len += 1;
+            return Ok(());
+        }
+
+        match self.transport.send_frame(&self.tx_frame) {
+            // byte is stored again when write is retried
+            Err(nb::Error::WouldBlock) => { Err(nb::Error::WouldBlock) }
+            result => {
+                self.tx_len = 0;
+                result
+            }
+        }
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> { Ok(()) }
+}
+
+impl<TransportType> ReadFrame for TransportSerial<TransportType>
+    where
+        TransportType: FrameTransport
+{
+    fn read_byte(&mut self) -> nb::Result<u8, Errors> {
+        if self.rx_index == FRAME_LEN {
+            match self.transport.recv_frame() {
+                Ok(frame) => {
+                    self.rx_frame = frame;
+                    self.rx_index = 0;
+                }
+                Err(nb::Error::WouldBlock) => { return Err(nb::Error::WouldBlock); }
+                Err(nb::Error::Other(_)) => { return Err(nb::Error::Other(Errors::ReadHardware)); }
+            }
+        }
+
+        let byte = self.rx_frame[self.rx_index];
+
+        self.rx_index += 1;
+
+        Ok(byte)
+    }
+}
+
+impl Reading {
+    /// Status byte as flags, see [`StatusFlags`] for caveats
+    pub const fn flags(&self) -> StatusFlags {
+        StatusFlags::from_byte(self.status)
+    }
+
+    ///
+    /// Validate (MH-Z19 start byte and checksum) and decode read concentration
+    /// response read elsewhere, no driver or serial needed. Floor and saturation
+    /// flags use defaults (400 ppm, factory 5000 ppm range).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let frame: [u8; 9] = read_frame_somehow();
+    ///
+    /// let reading: Reading = Reading::decode(frame).unwrap();
+    /// ```
+    pub fn decode(frame: [u8; FRAME_LEN]) -> Result<Reading, Errors> {
+        if frame[0] != START_BYTE {
+            return Err(Errors::StartByte);
+        }
+
+        if !frame_checksum_ok(&StandardChecksum, &frame) {
+            return Err(Errors::Checksum);
+        }
+
+        Ok(decode_reading(&frame, &DecodeVariant::STANDARD, DEFAULT_FLOOR_PPM, DEFAULT_RANGE.ppm()))
+    }
+
+    ///
+    /// Reading changed enough to be reported: concentration differs by at least
+    /// `ppm_threshold` ppm or temperature by at least `temp_threshold` °C. Temperature
+    /// becoming (un)available also counts as change. Status and flags are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let reading = mhz.measure().unwrap();
+    ///
+    /// if reading.significantly_differs_from(&last_sent, 50, 1) {
+    ///     radio.send(&reading);
+    ///     last_sent = reading;
+    /// }
+    /// ```
+    pub fn significantly_differs_from(
+        &self,
+        other: &Reading,
+        ppm_threshold: u16,
+        temp_threshold: i16
+    ) -> bool {
+        if self.co2.abs_diff(other.co2) >= ppm_threshold {
+            return true;
+        }
+
+        match (self.temperature, other.temperature) {
+            (Some(a), Some(b)) => { a.abs_diff(b) >= temp_threshold.unsigned_abs() }
+            (None, None) => { false }
+            _ => { true }
+        }
+    }
+
+    ///
+    /// Format reading as CSV line `timestamp,co2,temperature,status` for data logging:
+    /// `timestamp` as given, concentration in ppm, temperature in °C (empty field if
+    /// `None`) and status byte, all decimal. No header and no line terminator.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let reading = mhz.measure().unwrap();
+    ///
+    /// // "1700000000,1200,24,64"
+    /// log.write_line(&reading.to_csv_line(rtc.now()));
+    /// ```
+    pub fn to_csv_line(&self, timestamp: u32) -> heapless::String<consts::U48> {
+        use core::fmt::Write as _;
+
+        let mut line: heapless::String<consts::U48> = heapless::String::new();
+
+        // Longest line is 27 bytes, so writes can't fail
+        let _ = write!(line, "{},{},", timestamp, self.co2);
+
+        if let Some(temperature) = self.temperature {
+            let _ = write!(line, "{}", temperature);
+        }
+
+        let _ = write!(line, ",{}", self.status);
+
+        line
+    }
+}
+
+///
+/// Find first valid read concentration response (MH-Z19 start byte, 0x86 echo,
+/// checksum) in captured `data` and decode it like `Reading::decode()`. Frames are
+/// searched with [`FrameParser`] rules (stuffed start bytes, resync after wrong
+/// checksum). Returns offset of frame in `data` and the reading, `None` if there is
+/// no valid frame.
+///
+/// # Example
+///
+/// ```
+/// let capture: &[u8] = logic_analyzer_dump();
+///
+/// if let Some((offset, reading)) = decode_first_frame(capture) {
+///     let rest = &capture[offset + FRAME_LEN..];
+/// }
+/// ```
+pub fn decode_first_frame(data: &[u8]) -> Option<(usize, Reading)> {
+    let mut parser = FrameParser::new(START_BYTE);
+
+    for (index, &byte) in data.iter().enumerate() {
+        let frame = match parser.push(byte) {
+            Some(frame) => { frame }
+            None => { continue; }
+        };
+
+        if frame[1] != Command::ReadConcentration as u8 {
+            continue;
+        }
+
+        if let Ok(reading) = Reading::decode(frame) {
+            return Some((index + 1 - FRAME_LEN, reading));
+        }
+    }
+
+    None
+}
+
+///
+/// Decode ASCII identifier (model, serial number) from response data: padding
+/// (NUL, space, 0xFF) is trimmed from both ends, more than 16 characters left or
+/// non-printable bytes inside give `Errors::InvalidResponse`.
+///
+/// # Example
+///
+/// ```
+/// let id = decode_identifier(&[0x00, b'A', b'B', b'1', 0xFF]).unwrap();
+///
+/// assert_eq!(id.as_str(), "AB1");
+/// ```
+pub fn decode_identifier(data: &[u8]) -> Result<heapless::String<consts::U16>, Errors> {
+    let is_padding = |byte: &u8| matches!(byte, 0x00 | b' ' | 0xFF);
+
+    let start = data.iter().position(|b| !is_padding(b)).unwrap_or(data.len());
+    let end = data.iter().rposition(|b| !is_padding(b)).map_or(start, |index| index + 1);
+
+    let mut identifier: heapless::String<consts::U16> = heapless::String::new();
+
+    for &byte in data[start..end].iter() {
+        if !(b' '..=b'~').contains(&byte) {
+            return Err(Errors::InvalidResponse);
+        }
+
+        if identifier.push(byte as char).is_err() {
+            return Err(Errors::InvalidResponse);
+        }
+    }
+
+    Ok(identifier)
+}
+
+// Check checksum of frame
+fn frame_checksum_ok(checksum: &dyn Checksum, frame: &[u8; BUFFER_SIZE]) -> bool {
+    let crc_index = BUFFER_SIZE - 1;
+
+    checksum.checksum(&frame[0..crc_index]) == frame[crc_index]
+}
+
+/// Sum of readings which never overflows: values saturate instead of panic in debug builds
+#[derive(Debug, Default, Clone, Copy)]
+struct Accumulator {
+    sum: u64,
+    count: u32
+}
+
+impl Accumulator {
+    fn push(&mut self, value: u16) {
+        self.sum = self.sum.saturating_add(value as u64);
+        self.count = self.count.saturating_add(1);
+    }
+
+    fn mean(&self) -> Option<u16> {
+        match self.count {
+            0 => { None }
+            count => { Some((self.sum / count as u64).min(u16::MAX as u64) as u16) }
+        }
+    }
+}
+
+/// Real concentration never goes below outdoor baseline
+const DEFAULT_FLOOR_PPM: u16 = 400;
+
+/// Factory range of sensor
+const DEFAULT_RANGE: Range = Range::_5000;
+
+/// Real concentration is never above the widest range
+const DEFAULT_MAX_PLAUSIBLE_PPM: u16 = 10000;
+
+///
+/// Positions of fields in read concentration response. Cheap clones don't
+/// always follow the datasheet layout. Indices must be inside of checksum-protected
+/// part of frame (`co2` at most 6, others at most 7).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeVariant {
+    /// Index of concentration high byte, low byte follows it
+    pub co2: usize,
+    /// Index of temperature byte
+    pub temperature: usize,
+    /// Index of status byte
+    pub status: usize
+}
+
+impl DecodeVariant {
+    /// Datasheet layout: `FF 86 HH LL TT SS .. .. CS`
+    pub const STANDARD: DecodeVariant = DecodeVariant { co2: 2, temperature: 4, status: 5 };
+
+    /// Clones which send address before command echo, so data is shifted by one byte:
+    /// `FF 01 86 HH LL TT SS .. CS`
+    pub const SHIFTED: DecodeVariant = DecodeVariant { co2: 3, temperature: 5, status: 6 };
+
+    ///
+    /// Index of sensor address byte, between start byte and command echo (which
+    /// precedes concentration). `None` if echo directly follows start byte, like in
+    /// datasheet layout.
+    pub const fn address(&self) -> Option<usize> {
+        match self.co2 {
+            0..=2 => { None }
+            co2 => { Some(co2 - 2) }
+        }
+    }
+}
+
+impl Default for DecodeVariant {
+    fn default() -> Self {
+        DecodeVariant::STANDARD
+    }
+}
+
+/// 16 bit value from high and low byte, datasheet byte order used by the driver
+pub const fn decode_u16(hi: u8, lo: u8) -> u16 {
+    ((hi as u16) << 8) | lo as u16
+}
+
+/// 16 bit value from low and high byte, for clones sending little-endian values
+pub const fn decode_u16_le(lo: u8, hi: u8) -> u16 {
+    decode_u16(hi, lo)
+}
+
+// Decode read concentration response, values below `floor_ppm` and at `full_scale_ppm`
+// are flagged. Never panics: indices of custom variant outside of frame read as zero.
+fn decode_reading(
+    frame: &[u8; BUFFER_SIZE],
+    variant: &DecodeVariant,
+    floor_ppm: u16,
+    full_scale_ppm: u16
+) -> Reading {
+    let co2 = decode_u16(frame_byte(frame, variant.co2), frame_byte(frame, variant.co2.saturating_add(1)));
+
+    Reading {
+        co2,
+        temperature: decode_temperature(frame_byte(frame, variant.temperature)),
+        status: frame_byte(frame, variant.status),
+        below_floor: co2 < floor_ppm,
+        saturated: co2 >= full_scale_ppm
+    }
+}
+
+// Byte `index` of frame, zero outside of it
+fn frame_byte(frame: &[u8; BUFFER_SIZE], index: usize) -> u8 {
+    frame.get(index).copied().unwrap_or(0)
+}
+
+/// Offset of temperature byte in response and log record
+const TEMPERATURE_OFFSET: i16 = 40;
+
+/// Plausible temperature band, °C
+const TEMPERATURE_MIN: i16 = -40;
+const TEMPERATURE_MAX: i16 = 125;
+
+// Temperature of byte without plausibility check, always within -40..=215
+fn raw_temperature(byte: u8) -> i16 {
+    i16::from(byte).saturating_sub(TEMPERATURE_OFFSET)
+}
+
+// Decode temperature byte, `None` if value is not plausible
+fn decode_temperature(byte: u8) -> Option<i16> {
+    let temperature = raw_temperature(byte);
+
+    if (TEMPERATURE_MIN..=TEMPERATURE_MAX).contains(&temperature) {
+        Some(temperature)
+    } else {
+        None
+    }
+}
+
+/// Parts per million in one percent by volume
+#[cfg(feature = "float")]
+const PPM_PER_PERCENT: f32 = 10_000.0;
+
+/// Magnus formula coefficients (Sonntag 1990)
+#[cfg(feature = "float")]
+const MAGNUS_B: f32 = 17.62;
+#[cfg(feature = "float")]
+const MAGNUS_C: f32 = 243.12;
+
+///
+/// Dew point in °C from temperature (e.g. `Reading::temperature`) and relative
+/// humidity in % measured by external sensor, using Magnus formula. Formula is
+/// valid for -45..=60 °C with error below 0.35 °C; keep in mind MH-Z19 temperature
+/// is itself rough (±2 °C or worse). Humidity above 100 % is taken as 100 %,
+/// zero, negative or NaN humidity gives NaN.
+///
+/// Available with the `float` feature.
+///
+/// # Example
+///
+/// ```
+/// let reading = mhz.measure().unwrap();
+/// let humidity: f32 = hygrometer.read().unwrap();
+///
+/// if let Some(temperature) = reading.temperature {
+///     let dew_point = dew_point_c(temperature, humidity);
+/// }
+/// ```
+#[cfg(feature = "float")]
+pub fn dew_point_c(temp_c: i16, rel_humidity: f32) -> f32 {
+    if rel_humidity.is_nan() || rel_humidity <= 0.0 {
+        return f32::NAN;
+    }
+
+    let temp_c = temp_c as f32;
+    let gamma = ln(rel_humidity.min(100.0) / 100.0) + MAGNUS_B * temp_c / (MAGNUS_C + temp_c);
+
+    MAGNUS_C * gamma / (MAGNUS_B - gamma)
+}
+
+// Natural logarithm of positive normal `x`, core has no float math. Splits `x` into
+// mantissa and exponent and sums atanh series for mantissa, error is below 1e-6.
+#[cfg(feature = "float")]
+fn ln(x: f32) -> f32 {
+    let bits = x.to_bits();
+    let exponent = ((bits >> 23) & 0xFF) as i32 - 127;
+    let mantissa = f32::from_bits((bits & 0x007F_FFFF) | 0x3F80_0000);
+
+    let s = (mantissa - 1.0) / (mantissa + 1.0);
+    let s2 = s * s;
+    let series = s * (1.0 + s2 * (1.0 / 3.0 + s2 * (1.0 / 5.0 + s2 * (1.0 / 7.0 + s2 / 9.0))));
+
+    exponent as f32 * core::f32::consts::LN_2 + 2.0 * series
+}
+
+/// CO2 exhaled by seated adult, m³/h (ASHRAE 62.1 ballpark)
+#[cfg(feature = "float")]
+const CO2_PER_PERSON_M3_H: f32 = 0.018;
+
+///
+/// Rough number of people in room from steady-state CO2 balance: generation by
+/// occupants equals removal by ventilation, `n = (co2 - outdoor) * volume * ach / G`
+/// with 400 ppm outdoor air and `G` = 18 l/h per seated adult. Only valid once
+/// concentration has settled (about 3 / `ventilation_ach` hours after occupancy
+/// changed) and for well mixed air; activity, children and ventilation estimate
+/// easily shift result by 50 %. Negative results (and NaN) are clamped to zero.
+///
+/// Available with the `float` feature.
+///
+/// # Example
+///
+/// ```
+/// let co2 = mhz.co2().unwrap();
+///
+/// // 60 m³ office, 1.5 air changes per hour
+/// let people = estimate_occupancy(co2, 60.0, 1.5);
+/// ```
+#[cfg(feature = "float")]
+pub fn estimate_occupancy(co2_ppm: u16, room_volume_m3: f32, ventilation_ach: f32) -> f32 {
+    let excess = (co2_ppm as f32 - DEFAULT_FLOOR_PPM as f32) / 1_000_000.0;
+    let people = excess * room_volume_m3 * ventilation_ach / CO2_PER_PERSON_M3_H;
+
+    people.max(0.0)
+}
+
+///
+/// Decode record created by `Mhz19::log_record` into
+/// (timestamp, co2, temperature, status)
+pub fn decode_log_record(record: &[u8; 8]) -> (u32, u16, i16, u8) {
+    let timestamp = u32::from_be_bytes([record[0], record[1], record[2], record[3]]);
+    let co2 = decode_u16(record[4], record[5]);
+    let temperature = raw_temperature(record[6]);
+
+    (timestamp, co2, temperature, record[7])
+}
+
+/// Trait for implement mhz-19 logic
+pub trait Mhz19Trait {
+    type Error;
+
+    fn co2(&mut self) -> Result<u16, Self::Error>;
+    fn measure(&mut self) -> Result<Reading, Self::Error>;
+    fn calibrate_zero(&mut self) -> Result<(), Self::Error>;
+    fn calibrate_span(&mut self, ppm: u16) -> Result<(), Self::Error>;
+    fn auto_calibration(&mut self, state: AutoCalibrationState) -> Result<(), Self::Error>;
+    fn read_auto_calibration(&mut self) -> Result<AutoCalibrationState, Self::Error>;
+    fn range(&mut self, range: Range) -> Result<(), Self::Error>;
+    fn read_range(&mut self) -> Result<Range, Self::Error>;
+    fn supports(&self, cmd: Command) -> bool;
+}
+
+const BUFFER_SIZE: usize = FRAME_LEN;
+
+/// Maximum bytes dropped while searching for frame start
+const MAX_SKIPPED_BYTES: usize = 2 * BUFFER_SIZE;
+
+/// Maximum bytes read from serial by `reset()` before giving up on draining
+const MAX_DRAINED_BYTES: usize = 4 * BUFFER_SIZE;
+
+/// Number of pings sent by `diagnose()`
+const DIAGNOSE_PINGS: usize = 5;
+
+/// Wait between zero calibration and verification reading, sensor updates
+/// the value every few seconds
+const CALIBRATION_VERIFY_MS: u16 = 5000;
+
+/// Part of `measure_with_quality()` score given for valid checksum
+pub const QUALITY_CHECKSUM_WEIGHT: u8 = 40;
+/// Part of `measure_with_quality()` score given for concentration within floor..full scale
+pub const QUALITY_RANGE_WEIGHT: u8 = 30;
+/// Part of `measure_with_quality()` score given for plausible temperature
+pub const QUALITY_TEMPERATURE_WEIGHT: u8 = 15;
+/// Part of `measure_with_quality()` score given for status without error flag
+pub const QUALITY_STATUS_WEIGHT: u8 = 15;
+
+const _: () = assert!(
+    QUALITY_CHECKSUM_WEIGHT as u16
+        + QUALITY_RANGE_WEIGHT as u16
+        + QUALITY_TEMPERATURE_WEIGHT as u16
+        + QUALITY_STATUS_WEIGHT as u16
+        == 100
+);
+
+/// Wait between single-shot trigger and reading, one measurement cycle of sensor
+const SINGLE_SHOT_MS: u16 = 1000;
+
+/// Time sensor needs to reinitialize after factory reset
+const FACTORY_RESET_MS: u16 = 5000;
+
+/// Interval between reads of polling helpers (warm-up wait, `read_until()`)
+const POLL_INTERVAL_MS: u16 = 1000;
+
+/// Delay which does nothing, used when no delay is configured
+pub struct NoDelay;
+
+impl DelayMs<u16> for NoDelay {
+    fn delay_ms(&mut self, _ms: u16) {}
+}
+
+///
+/// Mhz-19 implementation of the driver for transmission for serial.
+/// Need set serial baudrate 9600
+///
+/// # Example
+///
+/// ```
+/// let serial = Serial::new(...);
+/// let mut mhz = Mhz19::new(serial);
+///
+/// let co2: u16 = mhz.co2().unwrap();
+/// ```
+pub struct Mhz19<SerialType, DelayType = NoDelay>
+    where
+        SerialType: Write<u8> + ReadFrame,
+        DelayType: DelayMs<u16>
+{
+    serial: SerialType,
+    delay: DelayType,
+    settle_ms: u16,
+    turnaround_ms: u16,
+    auto_drain: bool,
+    protocol: Protocol,
+    checksum: &'static dyn Checksum,
+    model: Model,
+    decode_variant: DecodeVariant,
+    floor_ppm: u16,
+    max_plausible_ppm: u16,
+    range: Range,
+    buffer: [u8; BUFFER_SIZE],
+    read_polls: u32,
+    max_read_polls: Option<u32>,
+    ready_gate: bool,
+    ready: bool,
+    nb_pending: bool,
+    nb_scanned: u8,
+    startup_grace: u8,
+    byte_swap_check: bool,
+    address_scan: u8,
+    last_error: Option<Errors>,
+    tx_frame: [u8; BUFFER_SIZE],
+    tx_index: usize,
+    parser: FrameParser,
+    rx_result: Option<Result<Reading, Errors>>,
+    last_accepted: Option<u16>,
+    #[cfg(feature = "float")]
+    smoothed: Option<f32>
+}
+
+///
+/// Builder for [`Mhz19`] with non-default settings
+///
+/// # Example
+///
+/// ```
+/// let serial = Serial::new(...);
+/// let delay = Delay::new(...);
+///
+/// let mut mhz = Mhz19::builder(serial)
+///     .model(Model::C)
+///     .settle_delay(delay, 100)
+///     .build();
+/// ```
+pub struct Mhz19Builder<SerialType, DelayType = NoDelay>
+    where
+        SerialType: Write<u8> + ReadFrame,
+        DelayType: DelayMs<u16>
+{
+    serial: SerialType,
+    delay: DelayType,
+    settle_ms: u16,
+    turnaround_ms: u16,
+    auto_drain: bool,
+    protocol: Protocol,
+    checksum: &'static dyn Checksum,
+    model: Model,
+    decode_variant: DecodeVariant
+}
+
+impl<SerialType> Mhz19Builder<SerialType, NoDelay>
+    where
+        SerialType: Write<u8> + ReadFrame
+{
+    pub const fn new(serial: SerialType) -> Self {
+        Self {
+            serial,
+            delay: NoDelay,
+            settle_ms: 0,
+            turnaround_ms: 0,
+            auto_drain: false,
+            protocol: Protocol::MHZ19,
+            checksum: &StandardChecksum,
+            model: Model::B,
+            decode_variant: DecodeVariant::STANDARD
+        }
+    }
+}
+
+impl<SerialType, DelayType> Mhz19Builder<SerialType, DelayType>
+    where
+        SerialType: Write<u8> + ReadFrame,
+        DelayType: DelayMs<u16>
+{
+    /// Set framing bytes (MH-Z19 by default)
+    pub fn protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Set checksum algorithm of frames (datasheet algorithm by default)
+    pub fn checksum(mut self, checksum: &'static dyn Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Set sensor model (MH-Z19B by default)
+    pub fn model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Set layout of read concentration response (datasheet layout by default)
+    pub fn decode_variant(mut self, decode_variant: DecodeVariant) -> Self {
+        self.decode_variant = decode_variant;
+        self
+    }
+
+    /// Set delay used for settle and turnaround times (no delay by default)
+    pub fn delay<NewDelayType>(self, delay: NewDelayType) -> Mhz19Builder<SerialType, NewDelayType>
+        where
+            NewDelayType: DelayMs<u16>
+    {
+        Mhz19Builder {
+            serial: self.serial,
+            delay,
+            settle_ms: self.settle_ms,
+            turnaround_ms: self.turnaround_ms,
+            auto_drain: self.auto_drain,
+            protocol: self.protocol,
+            checksum: self.checksum,
+            model: self.model,
+            decode_variant: self.decode_variant
+        }
+    }
+
+    ///
+    /// Wait `settle_ms` after write-only commands (`range()`, `auto_calibration()`),
+    /// otherwise next command right after them can see stale configuration.
+    /// Community testing shows ~100 ms is enough. Zero disables the delay.
+    /// Needs delay set with `delay()`.
+    pub fn settle_ms(mut self, settle_ms: u16) -> Self {
+        self.settle_ms = settle_ms;
+        self
+    }
+
+    ///
+    /// Set delay and settle time in one call, see `settle_ms()`
+    pub fn settle_delay<NewDelayType>(
+        self,
+        delay: NewDelayType,
+        settle_ms: u16
+    ) -> Mhz19Builder<SerialType, NewDelayType>
+        where
+            NewDelayType: DelayMs<u16>
+    {
+        self.delay(delay).settle_ms(settle_ms)
+    }
+
+    ///
+    /// Wait `turnaround_ms` after command is sent before reading response. On shared
+    /// single-wire setups the sensor needs a few ms, otherwise the tail of own
+    /// transmission is read back. Zero (default) disables the delay, which is right
+    /// for normal full-duplex UARTs. Needs delay set with `delay()`.
+    pub fn turnaround_ms(mut self, turnaround_ms: u16) -> Self {
+        self.turnaround_ms = turnaround_ms;
+        self
+    }
+
+    ///
+    /// Discard pending serial input before every command, so response left over
+    /// from timed out transaction is not taken as answer to the next one. Covers
+    /// blocking commands and transactions started by `read_co2()`; with
+    /// `start_measure()` serial isn't owned by driver, bytes fed before it are
+    /// dropped anyway. Off by default: draining costs a read poll per command.
+    pub fn auto_drain(mut self, auto_drain: bool) -> Self {
+        self.auto_drain = auto_drain;
+        self
+    }
+
+    pub fn build(self) -> Mhz19<SerialType, DelayType> {
+        Mhz19::from_parts(
+            self.serial,
+            self.delay,
+            self.settle_ms,
+            self.turnaround_ms,
+            self.auto_drain,
+            self.protocol,
+            self.checksum,
+            self.model,
+            self.decode_variant
+        )
+    }
+}
+
+impl<SerialType> Mhz19<SerialType, NoDelay>
+    where
+        SerialType: Write<u8> + ReadFrame
+{
+    ///
+    /// Create driver with default settings. `const`, so driver can be placed
+    /// in static storage.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// static mut MHZ: Option<Mhz19<Serial>> = None;
+    ///
+    /// let serial = Serial::new(...);
+    /// unsafe { MHZ = Some(Mhz19::new(serial)); }
+    /// ```
+    pub const fn new(serial: SerialType) -> Self {
+        Self::from_parts(
+            serial,
+            NoDelay,
+            0,
+            0,
+            false,
+            Protocol::MHZ19,
+            &StandardChecksum,
+            Model::B,
+            DecodeVariant::STANDARD
+        )
+    }
+
+    /// Start building driver with non-default settings
+    pub const fn builder(serial: SerialType) -> Mhz19Builder<SerialType, NoDelay> {
+        Mhz19Builder::new(serial)
+    }
+
+    ///
+    /// Create driver for sensor with non-standard framing bytes
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let protocol = Protocol { start_byte: 0x42, address: 0x01 };
+    /// let mut mhz = Mhz19::with_protocol(serial, protocol);
+    /// ```
+    pub fn with_protocol(serial: SerialType, protocol: Protocol) -> Self {
+        Self::builder(serial).protocol(protocol).build()
+    }
+
+    ///
+    /// Create driver and apply `config` to sensor, see `apply_config()`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::with_config(serial, SensorConfig::default()).unwrap();
+    /// ```
+    pub fn with_config(serial: SerialType, config: SensorConfig) -> Result<Self, Errors> {
+        let mut mhz = Self::new(serial);
+
+        mhz.apply_config(&config)?;
+
+        Ok(mhz)
+    }
+
+    ///
+    /// Create driver and set detection range of sensor, so `full_scale()` is right
+    /// from the start
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::with_range(serial, Range::_2000).unwrap();
+    /// ```
+    pub fn with_range(serial: SerialType, range: Range) -> Result<Self, Errors> {
+        let mut mhz = Self::new(serial);
+
+        mhz.range(range)?;
+
+        Ok(mhz)
+    }
+
+    ///
+    /// Create driver and check that sensor answers. Performs one serial round-trip
+    /// (read concentration) and returns `Errors::NoSensor` if it fails.
+    /// Use `new()` for lazy initialization.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new_checked(serial).unwrap();
+    /// ```
+    pub fn new_checked(serial: SerialType) -> Result<Self, Errors> {
+        let mut mhz = Self::new(serial);
+
+        match mhz.ping() {
+            Ok(_) => { Ok(mhz) }
+            Err(_) => { mhz.fail(Errors::NoSensor) }
+        }
+    }
+}
+
+impl<SerialType, DelayType> Mhz19<SerialType, DelayType>
+    where
+        SerialType: Write<u8> + ReadFrame,
+        DelayType: DelayMs<u16>
+{
+    /// Set sensor model (MH-Z19B by default)
+    pub fn set_model(&mut self, model: Model) {
+        self.model = model;
+    }
+
+    /// Get configured sensor model
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    ///
+    /// Set layout of read concentration response (datasheet layout by default).
+    /// Layout without address byte disables filter of `set_address_filter()`.
+    pub fn set_decode_variant(&mut self, decode_variant: DecodeVariant) {
+        self.decode_variant = decode_variant;
+
+        if decode_variant.address().is_none() {
+            self.address_scan = 0;
+        }
+    }
+
+    ///
+    /// Set concentration floor, readings below it are flagged with `Reading::below_floor`.
+    /// Default is 400 ppm (outdoor baseline), 0 disables the check.
+    pub fn set_floor(&mut self, ppm: u16) {
+        self.floor_ppm = ppm;
+    }
+
+    ///
+    /// Set maximum plausible concentration, `measure()` (and `co2()`) fail with
+    /// `Errors::ImplausibleValue` for readings above it. Some clones occasionally
+    /// report values like 60000 ppm. Default is 10000 ppm, `u16::MAX` disables the check.
+    pub fn set_max_plausible(&mut self, ppm: u16) {
+        self.max_plausible_ppm = ppm;
+    }
+
+    ///
+    /// Gate readings on warm-up: while enabled, `measure()` (and `co2()` and
+    /// everything built on it) fails with `Errors::NotReady` until `wait_until_ready()`
+    /// (or `co2_blocking_until_valid()`) saw a non warm-up value once. Prevents showing
+    /// warm-up garbage after boot; `force_read()` reads anyway. `new_checked()` only
+    /// checks that sensor answers and does not confirm readiness. Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut delay = Delay::new(...);
+    /// let mut mhz = Mhz19::new_checked(serial).unwrap();
+    ///
+    /// mhz.set_ready_gate(true);
+    /// mhz.wait_until_ready(&mut delay, 180_000).unwrap();
+    /// ```
+    pub fn set_ready_gate(&mut self, ready_gate: bool) {
+        self.ready_gate = ready_gate;
+    }
+
+    ///
+    /// Tolerate zero flood some units send for a fraction of a second after power-on:
+    /// during next `responses` responses a frame of only zero bytes (otherwise
+    /// `Errors::StartByte`) is discarded and `Errors::NotReady` returned, so boot code
+    /// can treat it like warm-up. Zero (default) disables the grace window.
+    pub fn set_startup_grace(&mut self, responses: u8) {
+        self.startup_grace = responses;
+    }
+
+    ///
+    /// Check readings for byte-swapped concentration, a quirk of some clones: reading
+    /// above configured range (`full_scale()`) which is within it with swapped bytes
+    /// fails with `Errors::PossibleByteSwap`. Consider `DecodeVariant` or another
+    /// driver for such sensor. Disabled by default.
+    pub fn set_byte_swap_check(&mut self, byte_swap_check: bool) {
+        self.byte_swap_check = byte_swap_check;
+    }
+
+    ///
+    /// Accept only responses addressed to this sensor on a shared (RS-485 multidrop) bus.
+    /// For clones which send address before command echo (`DecodeVariant::SHIFTED`):
+    /// frames whose address byte isn't `Protocol::address` are discarded, at most
+    /// `scan_frames` frames are read per response, then `Errors::UnexpectedAddress`.
+    /// Zero (default) disables filtering. Fails with `Errors::InvalidArgument` if
+    /// configured layout has no address byte (`DecodeVariant::address()`).
+    pub fn set_address_filter(&mut self, scan_frames: u8) -> Result<(), Errors> {
+        if scan_frames > 0 && self.decode_variant.address().is_none() {
+            return self.fail(Errors::InvalidArgument);
+        }
+
+        self.address_scan = scan_frames;
+
+        Ok(())
+    }
+
+    ///
+    /// Limit `WouldBlock` polls while waiting for response. Over the limit reads fail
+    /// with `Errors::ReadWouldBlockTimeout` instead of blocking forever on a silent
+    /// sensor. `None` (default) waits forever.
+    pub fn set_read_timeout_polls(&mut self, max_polls: Option<u32>) {
+        self.max_read_polls = max_polls;
+    }
+
+    // Create driver from builder settings, const so `new()` can be const
+    #[allow(clippy::too_many_arguments)]
+    const fn from_parts(
+        serial: SerialType,
+        delay: DelayType,
+        settle_ms: u16,
+        turnaround_ms: u16,
+        auto_drain: bool,
+        protocol: Protocol,
+        checksum: &'static dyn Checksum,
+        model: Model,
+        decode_variant: DecodeVariant
+    ) -> Self {
+        Self {
+            serial,
+            delay,
+            settle_ms,
+            turnaround_ms,
+            auto_drain,
+            protocol,
+            checksum,
+            model,
+            decode_variant,
+            floor_ppm: DEFAULT_FLOOR_PPM,
+            max_plausible_ppm: DEFAULT_MAX_PLAUSIBLE_PPM,
+            range: DEFAULT_RANGE,
+            buffer: [0; BUFFER_SIZE],
+            read_polls: 0,
+            max_read_polls: None,
+            ready_gate: false,
+            ready: false,
+            nb_pending: false,
+            nb_scanned: 0,
+            startup_grace: 0,
+            byte_swap_check: false,
+            address_scan: 0,
+            last_error: None,
+            tx_frame: [0; BUFFER_SIZE],
+            tx_index: BUFFER_SIZE,
+            parser: FrameParser::with_checksum(protocol.start_byte, checksum),
+            rx_result: None,
+            last_accepted: None,
+            #[cfg(feature = "float")]
+            smoothed: None
+        }
+    }
+
+    ///
+    /// Upper limit of configured range, ppm. Driver tracks range set with `range()`
+    /// or read with `read_range()`, until then factory range (5000 ppm) is assumed.
+    /// Readings at this value are flagged with `Reading::saturated`.
+    pub fn full_scale(&self) -> u16 {
+        self.range.ppm()
+    }
+
+    ///
+    /// Time left until sensor is nominally warmed up, by model warm-up duration.
+    /// `elapsed_ms` is time since sensor power-on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// mhz.set_model(Model::C);
+    ///
+    /// let remaining_ms: u32 = mhz.warmup_remaining_ms(uptime_ms);
+    /// ```
+    pub fn warmup_remaining_ms(&self, elapsed_ms: u32) -> u32 {
+        self.model.warmup_ms().saturating_sub(elapsed_ms)
+    }
+
+    ///
+    /// Take last error returned by any driver method (and clear it).
+    /// Useful for diagnostic task when errors are swallowed somewhere in application.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let _ = mhz.co2();
+    ///
+    /// if let Some(error) = mhz.take_last_error() {
+    ///     // log error
+    /// }
+    /// ```
+    pub fn take_last_error(&mut self) -> Option<Errors> {
+        self.last_error.take()
+    }
+
+    ///
+    /// Release serial, e.g. to reconfigure pins for other purpose. Driver
+    /// and configured delay are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mhz = Mhz19::new(serial);
+    ///
+    /// let serial = mhz.free();
+    /// ```
+    pub fn free(self) -> SerialType {
+        self.serial
+    }
+
+    ///
+    /// Last frame in driver buffer: response of last command with response, or
+    /// sent frame after command without response (calibration, configuration).
+    /// Filled up to the failure point if last read failed.
+    pub fn last_frame(&self) -> &[u8; FRAME_LEN] {
+        &self.buffer
+    }
+
+    ///
+    /// Byte `index` of `last_frame()`, `None` if index is outside of frame
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// mhz.measure().unwrap();
+    ///
+    /// // datasheet: byte 4 is temperature + 40
+    /// let temperature_byte = mhz.response_field(4).unwrap();
+    /// ```
+    pub fn response_field(&self, index: usize) -> Option<u8> {
+        self.buffer.get(index).copied()
+    }
+
+    ///
+    /// Number of `WouldBlock` polls of serial while reading last response. Large
+    /// values mean the loop spins waiting for a slow UART, zero that response was
+    /// already buffered.
+    pub fn last_read_polls(&self) -> u32 {
+        self.read_polls
+    }
+
+    ///
+    /// Read gas concentration as percentage by volume: 10000 ppm = 1 %,
+    /// so e.g. 1200 ppm is 0.12 %.
+    ///
+    /// Available with the `float` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let co2: f32 = mhz.co2_percent().unwrap();
+    /// ```
+    #[cfg(feature = "float")]
+    pub fn co2_percent(&mut self) -> Result<f32, Errors> {
+        Ok(self.co2()? as f32 / PPM_PER_PERCENT)
+    }
+
+    ///
+    /// Read gas concentration smoothed with exponential moving average.
+    /// `alpha` is smoothing factor from 0.0 (keep previous value) to 1.0 (no smoothing),
+    /// values outside are clamped and NaN means no smoothing. First reading is returned as is.
+    ///
+    /// Available with the `float` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let co2: f32 = mhz.co2_smoothed(0.2).unwrap();
+    /// ```
+    #[cfg(feature = "float")]
+    pub fn co2_smoothed(&mut self, alpha: f32) -> Result<f32, Errors> {
+        let co2 = self.co2()? as f32;
+
+        // NaN would poison running state forever
+        let alpha = if alpha.is_nan() { 1.0 } else { alpha.clamp(0.0, 1.0) };
+
+        let result = match self.smoothed {
+            Some(previous) => { previous + alpha * (co2 - previous) }
+            None => { co2 }
+        };
+
+        self.smoothed = Some(result);
+
+        Ok(result)
+    }
+
+    ///
+    /// Read gas concentration rejecting glitches: reading which differs from the last
+    /// accepted one by more than `max_jump` ppm is read once again. Re-read is accepted
+    /// if it's close to the last accepted value or confirms the jump (within `max_jump`
+    /// of the first reading), so real step change is followed; otherwise
+    /// `Errors::InvalidResponse` is returned. First reading is always accepted.
+    /// This catches single corrupted frames which pass checksum.
+    /// `reset()` forgets last accepted value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let co2: u16 = mhz.co2_filtered(500).unwrap();
+    /// ```
+    pub fn co2_filtered(&mut self, max_jump: u16) -> Result<u16, Errors> {
+        let co2 = self.co2()?;
+
+        let last = match self.last_accepted {
+            Some(last) if co2.abs_diff(last) > max_jump => { last }
+            _ => {
+                self.last_accepted = Some(co2);
+                return Ok(co2);
+            }
+        };
+
+        let confirm = self.co2()?;
+
+        if confirm.abs_diff(last) > max_jump && confirm.abs_diff(co2) > max_jump {
+            return self.fail(Errors::InvalidResponse);
+        }
+
+        self.last_accepted = Some(confirm);
+
+        Ok(confirm)
+    }
+
+    ///
+    /// `ppm` is a warm-up sentinel value of configured model,
+    /// see `Model::warmup_sentinels()`
+    pub fn is_warmup_value(&self, ppm: u16) -> bool {
+        self.model.is_warmup_value(ppm)
+    }
+
+    ///
+    /// Read gas concentration and return `true` if sensor is still warming up
+    /// (answers with warm-up sentinel value of model)
+    pub fn is_warming_up(&mut self) -> Result<bool, Errors> {
+        let co2 = self.force_read()?;
+
+        Ok(self.is_warmup_value(co2))
+    }
+
+    ///
+    /// Read gas concentration skipping warm-up values. Sensor is polled every second
+    /// until it returns plausible reading or `max_ms` passed (`Errors::Timeout`).
+    /// Serial errors are returned immediately. Plausible reading confirms readiness
+    /// for `set_ready_gate()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut delay = Delay::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// // datasheet warm-up time is 3 minutes
+    /// let co2: u16 = mhz.co2_blocking_until_valid(&mut delay, 180_000).unwrap();
+    /// ```
+    pub fn co2_blocking_until_valid<WaitType>(
+        &mut self,
+        delay: &mut WaitType,
+        max_ms: u32
+    ) -> Result<u16, Errors>
+        where
+            WaitType: DelayMs<u16>
+    {
+        let mut elapsed_ms: u32 = 0;
+
+        loop {
+            let co2 = self.force_read()?;
+
+            if !self.is_warmup_value(co2) {
+                self.ready = true;
+                return Ok(co2);
+            }
+
+            if elapsed_ms >= max_ms {
+                return self.fail(Errors::Timeout);
+            }
+
+            delay.delay_ms(POLL_INTERVAL_MS);
+            elapsed_ms = elapsed_ms.saturating_add(POLL_INTERVAL_MS as u32);
+        }
+    }
+
+    ///
+    /// Wait until sensor is warmed up (returns non warm-up value) and open the gate
+    /// of `set_ready_gate()`, see `co2_blocking_until_valid()`
+    pub fn wait_until_ready<WaitType>(
+        &mut self,
+        delay: &mut WaitType,
+        max_ms: u32
+    ) -> Result<(), Errors>
+        where
+            WaitType: DelayMs<u16>
+    {
+        self.co2_blocking_until_valid(delay, max_ms)?;
+
+        Ok(())
+    }
+
+    ///
+    /// Read gas concentration even if `set_ready_gate()` gate is still closed,
+    /// value can be warm-up garbage
+    pub fn force_read(&mut self) -> Result<u16, Errors> {
+        Ok(self.measure_ungated()?.co2)
+    }
+
+    ///
+    /// Get gas concentration waiting `ms` milliseconds between command and response
+    /// instead of configured turnaround time. For units which echo part of the command
+    /// back if response is read immediately.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut delay = Delay::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let co2: u16 = mhz.co2_with_settle(&mut delay, 20).unwrap();
+    /// ```
+    pub fn co2_with_settle<WaitType>(&mut self, delay: &mut WaitType, ms: u16) -> Result<u16, Errors>
+        where
+            WaitType: DelayMs<u16>
+    {
+        self.check_ready()?;
+
+        let cmd = Command::ReadConcentration as u8;
+
+        self.command(cmd, [0; 5])?;
+        delay.delay_ms(ms);
+        self.response()?;
+
+        Ok(self.checked_response()?.co2)
+    }
+
+    // Fail with `Errors::NotReady` while gate of `set_ready_gate()` is closed
+    fn check_ready(&mut self) -> Result<(), Errors> {
+        if self.ready_gate && !self.ready {
+            return self.fail(Errors::NotReady);
+        }
+
+        Ok(())
+    }
+
+    // Measure without warm-up gate of `set_ready_gate()`
+    fn measure_ungated(&mut self) -> Result<Reading, Errors> {
+        self.transact(Command::ReadConcentration as u8, [0; 5])?;
+        self.checked_response()
+    }
+
+    // Decode read concentration response in buffer with checks shared by every read
+    // path: echo, byte swap and plausibility
+    fn checked_response(&mut self) -> Result<Reading, Errors> {
+        self.check_echo(Command::ReadConcentration as u8)?;
+
+        let reading = self.decode(&self.buffer);
+        let full_scale = self.full_scale();
+
+        if self.byte_swap_check && reading.co2 > full_scale && reading.co2.swap_bytes() <= full_scale {
+            return self.fail(Errors::PossibleByteSwap);
+        }
+
+        if reading.co2 > self.max_plausible_ppm {
+            return self.fail(Errors::ImplausibleValue);
+        }
+
+        Ok(reading)
+    }
+
+    ///
+    /// Poll gas concentration every second until `predicate` returns `true` for it,
+    /// at most `max_attempts` reads (`Errors::Timeout` after them). Serial errors are
+    /// returned immediately.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut delay = Delay::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// // ventilate until it's safe
+    /// fan.on();
+    /// let co2: u16 = mhz.read_until(|co2| co2 < 800, &mut delay, 600).unwrap();
+    /// fan.off();
+    /// ```
+    pub fn read_until<PredicateType, WaitType>(
+        &mut self,
+        mut predicate: PredicateType,
+        delay: &mut WaitType,
+        max_attempts: u32
+    ) -> Result<u16, Errors>
+        where
+            PredicateType: FnMut(u16) -> bool,
+            WaitType: DelayMs<u16>
+    {
+        for attempt in 0..max_attempts {
+            if attempt > 0 {
+                delay.delay_ms(POLL_INTERVAL_MS);
+            }
+
+            let co2 = self.co2()?;
+
+            if predicate(co2) {
+                return Ok(co2);
+            }
+        }
+
+        self.fail(Errors::Timeout)
+    }
+
+    ///
+    /// Get gas concentration and temperature for older MH-Z19B units where status
+    /// byte is unreliable. Same transaction as `measure()`, but status byte is
+    /// dropped so it can't be misinterpreted (e.g. as warm-up flag) and temperature
+    /// is returned as is, without plausibility check.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let (co2, temperature) = mhz.measure_legacy().unwrap();
+    /// ```
+    pub fn measure_legacy(&mut self) -> Result<(u16, i16), Errors> {
+        let reading = self.measure()?;
+
+        Ok((reading.co2, raw_temperature(frame_byte(&self.buffer, self.decode_variant.temperature))))
+    }
+
+    ///
+    /// Get only temperature in Celsius (`None` if implausible). Protocol has no separate
+    /// temperature command, so this is the same 0x86 transaction as `measure()` with
+    /// CO2 part dropped; it only makes intent explicit on call side.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let temperature = mhz.temperature_only().unwrap();
+    /// ```
+    pub fn temperature_only(&mut self) -> Result<Option<i16>, Errors> {
+        Ok(self.measure()?.temperature)
+    }
+
+    ///
+    /// Get temperature in Fahrenheit, converted from the same Celsius value as
+    /// `temperature_only()`. Implausible temperature gives `Errors::InvalidResponse`.
+    ///
+    /// Available with the `float` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let temperature: f32 = mhz.temperature_f().unwrap();
+    /// ```
+    #[cfg(feature = "float")]
+    pub fn temperature_f(&mut self) -> Result<f32, Errors> {
+        match self.temperature_only()? {
+            Some(temperature) => { Ok(temperature as f32 * 9.0 / 5.0 + 32.0) }
+            None => { self.fail(Errors::InvalidResponse) }
+        }
+    }
+
+    ///
+    /// Get gas concentration together with raw response frame it was decoded from,
+    /// e.g. for audit logs. Same as `co2()` followed by `last_frame()`, but the frame
+    /// can't be replaced by another command in between.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let (co2, frame) = mhz.co2_with_frame().unwrap();
+    /// ```
+    pub fn co2_with_frame(&mut self) -> Result<(u16, [u8; FRAME_LEN]), Errors> {
+        let co2 = self.measure()?.co2;
+
+        Ok((co2, self.buffer))
+    }
+
+    ///
+    /// Get gas concentration paired with `now_us`, caller's timestamp in microseconds
+    /// taken right before this call, i.e. before the blocking transaction begins
+    /// (reading itself is up to one measurement cycle older). Timestamp is returned
+    /// unchanged, only on success.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let (co2, timestamp_us) = mhz.co2_timed(clock.now_us()).unwrap();
+    /// ```
+    pub fn co2_timed(&mut self, now_us: u64) -> Result<(u16, u64), Errors> {
+        Ok((self.co2()?, now_us))
+    }
+
+    ///
+    /// Decode read concentration responses sent to another master without sending
+    /// anything, e.g. for sniffing. Blocks until valid response (start byte, checksum,
+    /// 0x86 echo) is received; corrupted frames and responses to other commands are
+    /// skipped. Serial errors and failed value checks of `measure()` (byte swap,
+    /// plausibility) are returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// loop {
+    ///     let reading = mhz.listen().unwrap();
+    /// }
+    /// ```
+    pub fn listen(&mut self) -> Result<Reading, Errors> {
+        loop {
+            match self.response() {
+                Ok(_) => {
+                    if self.echo() == Command::ReadConcentration as u8 {
+                        return self.checked_response();
+                    }
+                }
+                Err(Errors::Checksum) | Err(Errors::StartByte) => {}
+                Err(error) => { return Err(error); }
+            }
+        }
+    }
+
+    ///
+    /// Get gas concentration without discarding frames with wrong checksum, for lossy links.
+    /// Returns value and `true` if checksum matched. Besides serial errors and wrong start
+    /// byte only checks of `measure()` (ready gate, startup grace, address filter, echo,
+    /// byte swap, plausibility) fail it. Application should filter unverified values itself.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let (co2, checksum_ok) = mhz.co2_lenient().unwrap();
+    /// ```
+    pub fn co2_lenient(&mut self) -> Result<(u16, bool), Errors> {
+        let reading = self.measure_unverified()?;
+
+        Ok((reading.co2, self.checksum_ok()))
+    }
+
+    // Measure without checksum check, for `co2_lenient()` and `measure_with_quality()`
+    fn measure_unverified(&mut self) -> Result<Reading, Errors> {
+        self.check_ready()?;
+        self.command(Command::ReadConcentration as u8, [0; 5])?;
+        self.turnaround();
+        self.addressed_response(false)?;
+        self.checked_response()
+    }
+
+    ///
+    /// Get reading with quality score 0..=100, sum of weights of passed checks:
+    ///
+    /// | check                                          | weight |
+    /// |------------------------------------------------|--------|
+    /// | checksum is valid                              | 40     |
+    /// | concentration within floor..full scale         | 30     |
+    /// | temperature is plausible                       | 15     |
+    /// | no error in status (`StatusFlags::has_error`)  | 15     |
+    ///
+    /// Weights are `QUALITY_*_WEIGHT` constants. Like `co2_lenient()`, frame with wrong
+    /// checksum is not an error here, it only lowers the score; other checks of
+    /// `measure()` apply.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let (reading, quality) = mhz.measure_with_quality().unwrap();
+    ///
+    /// if quality >= 80 {
+    ///     dashboard.show(reading.co2);
+    /// }
+    /// ```
+    pub fn measure_with_quality(&mut self) -> Result<(Reading, u8), Errors> {
+        let reading = self.measure_unverified()?;
+        let mut quality: u8 = 0;
+
+        if self.checksum_ok() {
+            quality += QUALITY_CHECKSUM_WEIGHT;
+        }
+
+        if !reading.below_floor && !reading.saturated {
+            quality += QUALITY_RANGE_WEIGHT;
+        }
+
+        if reading.temperature.is_some() {
+            quality += QUALITY_TEMPERATURE_WEIGHT;
+        }
+
+        if !reading.flags().has_error() {
+            quality += QUALITY_STATUS_WEIGHT;
+        }
+
+        Ok((reading, quality))
+    }
+
+    ///
+    /// Send command and read response of unknown length: frame ends after up to
+    /// 9 bytes or when no byte arrives within `gap` (measured with `timer`, restarted
+    /// on every byte). Whatever was received is validated (start byte, last byte is
+    /// checksum of the rest) and returned. For query commands of firmware which
+    /// answers with short frames, fixed length reading would hang on them.
+    /// Returns `Errors::Timeout` if nothing is received.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut timer = Timer::tim2(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let frame: &[u8] = mhz.query(0x9B, [0; 5], &mut timer, 5.ms()).unwrap();
+    /// ```
+    pub fn query<TimerType, TimeType>(
+        &mut self,
+        cmd: u8,
+        data: [u8; 5],
+        timer: &mut TimerType,
+        gap: TimeType
+    ) -> Result<&[u8], Errors>
+        where
+            TimerType: CountDown,
+            TimeType: Into<TimerType::Time> + Copy
+    {
+        self.command(cmd, data)?;
+        self.turnaround();
+
+        let mut len: usize = 0;
+
+        timer.start(gap);
+
+        while len < BUFFER_SIZE {
+            match self.serial.read_byte() {
+                Ok(byte) => {
+                    self.buffer[len] = byte;
+                    len += 1;
+                    timer.start(gap);
+                }
+                Err(nb::Error::WouldBlock) => {
+                    if timer.wait().is_ok() {
+                        break;
+                    }
+                }
+                Err(nb::Error::Other(error)) => { return self.fail(error); }
+            }
+        }
+
+        if len == 0 {
+            return self.fail(Errors::Timeout);
+        }
+
+        if self.buffer[0] != self.protocol.start_byte {
+            return self.fail(Errors::StartByte);
+        }
+
+        let crc_index = len - 1;
+
+        if len < 2 || self.checksum.checksum(&self.buffer[..crc_index]) != self.buffer[crc_index] {
+            return self.fail(Errors::Checksum);
+        }
+
+        Ok(&self.buffer[..len])
+    }
+
+    ///
+    /// Read state of zero/span calibration. None of the supported models
+    /// (MH-Z19B, MH-Z19C) document a status query, so for them
+    /// `Errors::Unsupported` is returned.
+    pub fn calibration_status(&mut self) -> Result<CalStatus, Errors> {
+        match self.model {
+            Model::B | Model::C => { self.fail(Errors::Unsupported) }
+        }
+    }
+
+    ///
+    /// Read current measurement mode. MH-Z19B and MH-Z19C only measure
+    /// continuously, so no serial transaction is needed for them.
+    pub fn read_measurement_mode(&mut self) -> Result<MeasurementMode, Errors> {
+        match self.model {
+            Model::B | Model::C => { Ok(MeasurementMode::Continuous) }
+        }
+    }
+
+    ///
+    /// Set measurement mode. MH-Z19B and MH-Z19C have no single-shot mode,
+    /// so `MeasurementMode::SingleShot` returns `Errors::Unsupported` for them
+    /// and `MeasurementMode::Continuous` is accepted without sending anything.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// if mhz.set_measurement_mode(MeasurementMode::SingleShot).is_err() {
+    ///     // sensor can't save power, keep it continuous
+    /// }
+    /// ```
+    pub fn set_measurement_mode(&mut self, mode: MeasurementMode) -> Result<(), Errors> {
+        match (self.model, mode) {
+            (Model::B | Model::C, MeasurementMode::Continuous) => { Ok(()) }
+            (Model::B | Model::C, MeasurementMode::SingleShot) => { self.fail(Errors::Unsupported) }
+        }
+    }
+
+    ///
+    /// Set UART baud rate of sensor. Caller must reconfigure own UART to the new rate
+    /// afterwards, otherwise the sensor is unreachable. MH-Z19B and MH-Z19C have no
+    /// documented baud command and run at fixed 9600: `SensorBaud::_9600` is accepted
+    /// without sending anything, other rates return `Errors::Unsupported`.
+    ///
+    /// Available with the `experimental` feature, no supported model implements it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// if mhz.set_baud(SensorBaud::_19200).is_ok() {
+    ///     serial.set_baudrate(19200);
+    /// }
+    /// ```
+    #[cfg(feature = "experimental")]
+    pub fn set_baud(&mut self, baud: SensorBaud) -> Result<(), Errors> {
+        match (self.model, baud) {
+            (Model::B | Model::C, SensorBaud::_9600) => { Ok(()) }
+            (Model::B | Model::C, _) => { self.fail(Errors::Unsupported) }
+        }
+    }
+
+    ///
+    /// Set concentration at which onboard alarm output (relay, open collector) of
+    /// module triggers. Threshold above configured range (`full_scale()`) gives
+    /// `Errors::InvalidArgument`. MH-Z19B and MH-Z19C have no alarm output and no
+    /// documented command for it, so valid thresholds return `Errors::Unsupported`
+    /// without sending anything.
+    ///
+    /// Available with the `experimental` feature, no supported model implements it.
+    #[cfg(feature = "experimental")]
+    pub fn set_alarm_threshold(&mut self, ppm: u16) -> Result<(), Errors> {
+        if ppm > self.full_scale() {
+            return self.fail(Errors::InvalidArgument);
+        }
+
+        match self.model {
+            Model::B | Model::C => { self.fail(Errors::Unsupported) }
+        }
+    }
+
+    ///
+    /// Read alarm threshold set with `set_alarm_threshold()`, ppm. Returns
+    /// `Errors::Unsupported` for models without alarm output (MH-Z19B, MH-Z19C).
+    ///
+    /// Available with the `experimental` feature, no supported model implements it.
+    #[cfg(feature = "experimental")]
+    pub fn read_alarm_threshold(&mut self) -> Result<u16, Errors> {
+        match self.model {
+            Model::B | Model::C => { self.fail(Errors::Unsupported) }
+        }
+    }
+
+    ///
+    /// Read model or serial identifier string of sensor, see `decode_identifier()`.
+    /// MH-Z19B and MH-Z19C have no documented identifier command, so
+    /// `Errors::Unsupported` is returned without sending anything.
+    ///
+    /// Available with the `experimental` feature, no supported model implements it.
+    #[cfg(feature = "experimental")]
+    pub fn serial_number(&mut self) -> Result<heapless::String<consts::U16>, Errors> {
+        match self.model {
+            Model::B | Model::C => { self.fail(Errors::Unsupported) }
+        }
+    }
+
+    ///
+    /// Put sensor to low-power sleep (heater off). MH-Z19B and MH-Z19C have no sleep
+    /// command, so `Errors::Unsupported` is returned without sending anything. To
+    /// duty-cycle them switch their supply (e.g. with a MOSFET) instead; after power-on
+    /// readings settle only after `Model::warmup_ms()`, see `wait_until_ready()`.
+    ///
+    /// Available with the `experimental` feature, no supported model implements it.
+    #[cfg(feature = "experimental")]
+    pub fn sleep(&mut self) -> Result<(), Errors> {
+        match self.model {
+            Model::B | Model::C => { self.fail(Errors::Unsupported) }
+        }
+    }
+
+    ///
+    /// Wake sensor from `sleep()`. Returns `Errors::Unsupported` for models without
+    /// sleep command (MH-Z19B, MH-Z19C).
+    ///
+    /// Available with the `experimental` feature, no supported model implements it.
+    #[cfg(feature = "experimental")]
+    pub fn wake(&mut self) -> Result<(), Errors> {
+        match self.model {
+            Model::B | Model::C => { self.fail(Errors::Unsupported) }
+        }
+    }
+
+    ///
+    /// Read cumulative power-on time of sensor in hours, e.g. to plan replacement
+    /// before end of rated lifespan. Neither MH-Z19B nor MH-Z19C firmware is known
+    /// to expose it, so `Errors::Unsupported` is returned without sending anything.
+    ///
+    /// Available with the `experimental` feature, no supported model implements it.
+    #[cfg(feature = "experimental")]
+    pub fn operating_hours(&mut self) -> Result<u32, Errors> {
+        match self.model {
+            Model::B | Model::C => { self.fail(Errors::Unsupported) }
+        }
+    }
+
+    ///
+    /// Trigger measurement in single-shot mode. Returns `Errors::Unsupported`
+    /// for models without single-shot mode (MH-Z19B, MH-Z19C).
+    pub fn trigger_single_measurement(&mut self) -> Result<(), Errors> {
+        match self.model {
+            Model::B | Model::C => { self.fail(Errors::Unsupported) }
+        }
+    }
+
+    ///
+    /// Read gas concentration in single-shot mode: trigger measurement, wait one
+    /// measurement cycle (1 s) and read result. No model documents a conversion time
+    /// yet; for models without single-shot mode (MH-Z19B, MH-Z19C) `Errors::Unsupported`
+    /// is returned before waiting.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut delay = Delay::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let co2: u16 = mhz.co2_single_shot(&mut delay).unwrap();
+    /// ```
+    pub fn co2_single_shot<WaitType>(&mut self, delay: &mut WaitType) -> Result<u16, Errors>
+        where
+            WaitType: DelayMs<u16>
+    {
+        self.trigger_single_measurement()?;
+
+        delay.delay_ms(SINGLE_SHOT_MS);
+
+        self.co2()
+    }
+
+    ///
+    /// Read sensor and pack result into 8 byte record for compact logging:
+    /// timestamp (4 bytes), co2 (2 bytes), raw temperature byte (1 byte), status (1 byte),
+    /// all big-endian. Use `decode_log_record` to unpack.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let record: [u8; 8] = mhz.log_record(timestamp).unwrap();
+    /// ```
+    pub fn log_record(&mut self, timestamp: u32) -> Result<[u8; 8], Errors> {
+        let reading = self.measure()?;
+
+        let timestamp = timestamp.to_be_bytes();
+        let co2 = reading.co2.to_be_bytes();
+
+        Ok([
+            timestamp[0],
+            timestamp[1],
+            timestamp[2],
+            timestamp[3],
+            co2[0],
+            co2[1],
+            frame_byte(&self.buffer, self.decode_variant.temperature),
+            reading.status
+        ])
+    }
+
+    ///
+    /// Start interrupt driven read concentration transaction. Blocking `command()`
+    /// and `response()` are split into pieces for TX and RX interrupts:
+    /// TX interrupt sends bytes from `next_tx_byte()` until it returns `None`, RX interrupt
+    /// passes every received byte to `feed_byte()`, and result is taken with `poll_result()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// // RTIC: mhz is shared resource
+    /// fn periodic(cx) {
+    ///     cx.shared.mhz.lock(|mhz| mhz.start_measure());
+    ///     // enable TX interrupt
+    /// }
+    ///
+    /// fn uart_tx(cx) {
+    ///     match cx.shared.mhz.lock(|mhz| mhz.next_tx_byte()) {
+    ///         Some(byte) => { uart.write(byte); }
+    ///         None => { /* disable TX interrupt */ }
+    ///     }
+    /// }
+    ///
+    /// fn uart_rx(cx) {
+    ///     let byte = uart.read();
+    ///     cx.shared.mhz.lock(|mhz| mhz.feed_byte(byte));
+    /// }
+    ///
+    /// fn idle(cx) {
+    ///     if let Some(Ok(reading)) = cx.shared.mhz.lock(|mhz| mhz.poll_result()) { ... }
+    /// }
+    /// ```
+    pub fn start_measure(&mut self) {
+        self.tx_frame = build_frame(&self.protocol, self.checksum, Command::ReadConcentration as u8, [0; 5]);
+        self.tx_index = 0;
+        self.parser.reset();
+        self.rx_result = None;
+    }
+
+    /// Next byte of command frame to transmit, `None` when whole frame is sent
+    pub fn next_tx_byte(&mut self) -> Option<u8> {
+        let byte = self.tx_frame.get(self.tx_index).copied();
+
+        if byte.is_some() {
+            self.tx_index += 1;
+        }
+
+        byte
+    }
+
+    /// Pass received byte to driver
+    pub fn feed_byte(&mut self, byte: u8) {
+        if let Some(frame) = self.parser.push(byte) {
+            self.buffer = frame;
+
+            if !self.foreign_address() {
+                self.rx_result = Some(self.checked_response());
+            }
+        }
+    }
+
+    ///
+    /// Take result of transaction started with `start_measure()` if it's received.
+    /// Checks of `measure()` apply, except ready gate and startup grace.
+    pub fn poll_result(&mut self) -> Option<Result<Reading, Errors>> {
+        self.rx_result.take()
+    }
+
+    ///
+    /// Read gas concentration without blocking, with canonical `nb` signature, so it
+    /// can be driven by generic `nb`-based code (`nb::block!`, schedulers). First call
+    /// starts transaction, every call sends or receives as many bytes as serial accepts
+    /// without blocking and returns `WouldBlock` until response is complete. Shares
+    /// state with `start_measure()`, don't mix them. Checks of `measure()` (ready gate,
+    /// echo, address filter, byte swap, plausible maximum) apply. Startup grace has
+    /// nothing to catch here: zero flood before start byte is skipped while waiting.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// loop {
+    ///     match mhz.read_co2() {
+    ///         Ok(co2) => { /* show co2 */ }
+    ///         Err(nb::Error::WouldBlock) => { /* do other work */ }
+    ///         Err(nb::Error::Other(error)) => { /* log error */ }
+    ///     }
+    /// }
+    /// ```
+    pub fn read_co2(&mut self) -> nb::Result<u16, Errors> {
+        Ok(self.read_measurement()?.co2)
+    }
+
+    ///
+    /// Get gas concentration, temperature and status byte without blocking, same
+    /// transaction and checks as `read_co2()`
+    pub fn read_measurement(&mut self) -> nb::Result<Reading, Errors> {
+        if !self.nb_pending {
+            if let Err(error) = self.check_ready() {
+                return self.fail_nb(error);
+            }
+
+            if self.auto_drain {
+                self.drain();
+            }
+
+            self.start_measure();
+            self.nb_pending = true;
+            self.nb_scanned = 0;
+        }
+
+        while let Some(&byte) = self.tx_frame.get(self.tx_index) {
+            match self.serial.write(byte) {
+                Ok(_) => { self.tx_index += 1; }
+                Err(nb::Error::WouldBlock) => { return Err(nb::Error::WouldBlock); }
+                Err(nb::Error::Other(_)) => { return self.fail_nb(Errors::Write); }
+            }
+        }
+
+        loop {
+            let byte = match self.serial.read_byte() {
+                Ok(byte) => { byte }
+                Err(nb::Error::WouldBlock) => { return Err(nb::Error::WouldBlock); }
+                Err(nb::Error::Other(error)) => { return self.fail_nb(error); }
+            };
+
+            if let Some(frame) = self.parser.push(byte) {
+                self.buffer = frame;
+
+                if self.foreign_address() {
+                    self.nb_scanned += 1;
+
+                    if self.nb_scanned >= self.address_scan {
+                        return self.fail_nb(Errors::UnexpectedAddress);
+                    }
+
+                    continue;
+                }
+
+                self.nb_pending = false;
+
+                return self.checked_response().map_err(nb::Error::Other);
+            }
+        }
+    }
+
+    ///
+    /// Periodic async stream of readings taken every `interval`, see [`ReadingStream`]
+    ///
+    /// Available with the `async` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut mhz = Mhz19::new(serial);
+    /// let mut stream = mhz.stream(EmbassyTimer, Duration::from_secs(5));
+    ///
+    /// while let Some(result) = stream.next().await {
+    ///     // handle reading
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn stream<TimerType>(
+        &mut self,
+        timer: TimerType,
+        interval: Duration
+    ) -> ReadingStream<'_, SerialType, DelayType, TimerType>
+        where
+            TimerType: AsyncTimer
+    {
+        ReadingStream::new(self, timer, interval)
+    }
+
+    // Same as `fail()` for `nb` results, ends pending `read_co2()` transaction
+    fn fail_nb<T>(&mut self, error: Errors) -> nb::Result<T, Errors> {
+        self.nb_pending = false;
+        self.last_error = Some(error);
+
+        Err(nb::Error::Other(error))
+    }
+
+    ///
+    /// Read gas concentration `samples` times (at least once) and return mean value.
+    /// Sum is saturating, so pathological inputs can't overflow.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let co2: u16 = mhz.co2_averaged(5).unwrap();
+    /// ```
+    pub fn co2_averaged(&mut self, samples: u16) -> Result<u16, Errors> {
+        let mut accumulator = Accumulator::default();
+
+        for _ in 0..samples.max(1) {
+            accumulator.push(self.co2()?);
+        }
+
+        match accumulator.mean() {
+            Some(mean) => { Ok(mean) }
+            None => { self.fail(Errors::InvalidResponse) }
+        }
+    }
+
+    ///
+    /// Send a few pings and classify failures to find out why sensor doesn't work:
+    /// no response at all, garbage without start bytes (baudrate mismatch) or
+    /// some corrupted frames (noise). Like other methods it blocks while serial
+    /// has no data, so it needs serial which fails on read timeout to detect `NoResponse`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// match mhz.diagnose() {
+    ///     Diagnosis::BaudMismatch => { /* check serial baudrate */ }
+    ///     _ => {}
+    /// }
+    /// ```
+    pub fn diagnose(&mut self) -> Diagnosis {
+        let mut ok: usize = 0;
+        let mut no_response: usize = 0;
+        let mut start_byte_seen = false;
+
+        for _ in 0..DIAGNOSE_PINGS {
+            match self.ping() {
+                Ok(_) => {
+                    ok += 1;
+                    start_byte_seen = true;
+                }
+                Err(Errors::Read)
+                | Err(Errors::ReadHardware)
+                | Err(Errors::ReadWouldBlockTimeout) => { no_response += 1; }
+                Err(Errors::StartByte) => {}
+                Err(_) => { start_byte_seen = true; }
+            }
+        }
+
+        if ok == DIAGNOSE_PINGS {
+            Diagnosis::Ok
+        } else if no_response == DIAGNOSE_PINGS {
+            Diagnosis::NoResponse
+        } else if ok == 0 && !start_byte_seen {
+            Diagnosis::BaudMismatch
+        } else {
+            Diagnosis::Noisy
+        }
+    }
+
+    ///
+    /// Bring driver to known state after error: drain pending serial input (up to
+    /// `MAX_DRAINED_BYTES`), zero frame buffer, drop last error, progress of
+    /// non-blocking transaction and state of `co2_filtered()`. Sends nothing,
+    /// see `recover()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// if mhz.co2().is_err() {
+    ///     mhz.reset();
+    /// }
+    /// ```
+    pub fn reset(&mut self) {
+        self.drain();
+
+        self.buffer = [0; BUFFER_SIZE];
+        self.last_error = None;
+        self.tx_index = BUFFER_SIZE;
+        self.parser.reset();
+        self.rx_result = None;
+        self.nb_pending = false;
+        self.last_accepted = None;
+    }
+
+    ///
+    /// Reset driver (see `reset()`) and ping sensor to check that communication
+    /// is back in sync
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// if let Err(Errors::Checksum) = mhz.co2() {
+    ///     mhz.recover().unwrap();
+    /// }
+    /// ```
+    pub fn recover(&mut self) -> Result<(), Errors> {
+        self.reset();
+        self.ping()
+    }
+
+    // Discard pending serial input, up to `MAX_DRAINED_BYTES`
+    fn drain(&mut self) {
+        for _ in 0..MAX_DRAINED_BYTES {
+            if self.serial.read_byte().is_err() {
+                break;
+            }
+        }
+    }
+
+    // Decode read concentration response with configured layout and thresholds
+    fn decode(&self, frame: &[u8; BUFFER_SIZE]) -> Reading {
+        decode_reading(frame, &self.decode_variant, self.floor_ppm, self.full_scale())
+    }
+
+    // Command echo of response in buffer, it's right before data
+    fn echo(&self) -> u8 {
+        frame_byte(&self.buffer, self.decode_variant.co2.saturating_sub(1))
+    }
+
+    /// Store error as last error and return it
+    fn fail<T>(&mut self, error: Errors) -> Result<T, Errors> {
+        self.last_error = Some(error);
+
+        Err(error)
+    }
+
+    /// Send read concentration command and check that valid response received
+    pub fn ping(&mut self) -> Result<(), Errors> {
+        self.transact(Command::ReadConcentration as u8, [0; 5])
+    }
+
+    /// Send command to mhz-19 over serial
+    fn command(&mut self, cmd: u8, data: [u8; 5]) -> Result<(), Errors> {
+        if self.auto_drain {
+            self.drain();
+        }
+
+        self.buffer = build_frame(&self.protocol, self.checksum, cmd, data);
+
+        for &b in self.buffer.iter() {
+            if nb::block!(self.serial.write(b)).is_err() {
+                return self.fail(Errors::Write);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send command and read response, waiting configured turnaround time between.
+    /// Response must echo the command.
+    fn transact(&mut self, cmd: u8, data: [u8; 5]) -> Result<(), Errors> {
+        self.command(cmd, data)?;
+        self.turnaround();
+        self.response()?;
+        self.check_echo(cmd)
+    }
+
+    /// Check that response in buffer echoes `cmd`
+    fn check_echo(&mut self, cmd: u8) -> Result<(), Errors> {
+        let got = self.echo();
+
+        if got != cmd {
+            return self.fail(Errors::UnexpectedCommand { expected: cmd, got });
+        }
+
+        Ok(())
+    }
+
+    /// Wait configured turnaround time between command and response
+    fn turnaround(&mut self) {
+        if self.turnaround_ms > 0 {
+            self.delay.delay_ms(self.turnaround_ms);
+        }
+    }
+
+    /// Send command which has no response and wait configured settle time
+    fn write_command(&mut self, cmd: u8, data: [u8; 5]) -> Result<(), Errors> {
+        self.command(cmd, data)?;
+
+        if self.settle_ms > 0 {
+            self.delay.delay_ms(self.settle_ms);
+        }
+
+        Ok(())
+    }
+
+    /// Read response addressed to this sensor, see `set_address_filter()`
+    fn response(&mut self) -> Result<(), Errors> {
+        self.addressed_response(true)
+    }
+
+    // Read frames until one is addressed to this sensor, checksum is checked if `verify`
+    fn addressed_response(&mut self, verify: bool) -> Result<(), Errors> {
+        let mut scanned: u8 = 0;
+
+        loop {
+            self.response_frame(verify)?;
+
+            if !self.foreign_address() {
+                return Ok(());
+            }
+
+            scanned += 1;
+
+            if scanned >= self.address_scan {
+                return self.fail(Errors::UnexpectedAddress);
+            }
+        }
+    }
+
+    /// Frame in buffer is addressed to other sensor, see `set_address_filter()`
+    fn foreign_address(&self) -> bool {
+        match self.decode_variant.address() {
+            Some(index) if self.address_scan > 0 => { frame_byte(&self.buffer, index) != self.protocol.address }
+            _ => { false }
+        }
+    }
+
+    /// Read response from mhz-19 driver with check start byte and checksum (if `verify`)
+    fn response_frame(&mut self, verify: bool) -> Result<(), Errors> {
+        let in_grace = self.startup_grace > 0;
+
+        self.startup_grace = self.startup_grace.saturating_sub(1);
+
+        match self.receive() {
+            Ok(_) => {}
+            Err(Errors::StartByte) if in_grace && self.buffer.iter().all(|&b| b == 0) => {
+                return self.fail(Errors::NotReady);
+            }
+            Err(error) => { return Err(error); }
+        }
+
+        if verify && !self.checksum_ok() {
+            return self.fail(Errors::Checksum);
+        }
+
+        Ok(())
+    }
+
+    /// Read response from mhz-19 driver with check start byte only
+    fn receive(&mut self) -> Result<(), Errors> {
+        if let Err(error) = self.read_synced() {
+            return self.fail(error);
+        }
+
+        if self.buffer[0] != self.protocol.start_byte {
+            return self.fail(Errors::StartByte);
+        }
+
+        Ok(())
+    }
+
+    /// Check checksum of frame in buffer
+    fn checksum_ok(&self) -> bool {
+        frame_checksum_ok(self.checksum, &self.buffer)
+    }
+
+    ///
+    /// Read frame into buffer dropping bytes before start byte. Run of repeated
+    /// start bytes is collapsed (sensor sometimes stuffs extra 0xFF before frame)
+    /// and the last one is taken as frame start. Gives up after `MAX_SKIPPED_BYTES`,
+    /// start byte is checked by caller.
+    fn read_synced(&mut self) -> Result<(), Errors> {
+        self.read_polls = 0;
+        self.serial
+            .read_frame_polled(&mut self.buffer, &mut self.read_polls, self.max_read_polls)?;
+
+        let mut skipped: usize = 0;
+
+        while skipped < MAX_SKIPPED_BYTES {
+            let offset = self.frame_offset();
+
+            if offset == 0 {
+                break;
+            }
+
+            self.buffer.copy_within(offset.., 0);
+            self.serial
+                .read_frame_polled(
+                    &mut self.buffer[BUFFER_SIZE - offset..],
+                    &mut self.read_polls,
+                    self.max_read_polls
+                )?;
+
+            skipped += offset;
+        }
+
+        Ok(())
+    }
+
+    // Index of frame start in buffer or buffer size if there is no start byte
+    fn frame_offset(&self) -> usize {
+        let start_byte = self.protocol.start_byte;
+
+        for index in 0..BUFFER_SIZE {
+            if self.buffer[index] == start_byte && self.buffer.get(index + 1) != Some(&start_byte) {
+                return index;
+            }
+        }
+
+        BUFFER_SIZE
+    }
+
+    ///
+    /// Check auto calibration (ABC) state and return `true` if it's enabled.
+    ///
+    /// ABC assumes the sensor sees fresh air (~400 ppm) at least once a day.
+    /// In sealed environments (greenhouses etc.) the baseline drifts with ABC on,
+    /// so application can use this to show a warning.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// if mhz.warn_if_abc_enabled().unwrap() {
+    ///     // show warning
+    /// }
+    /// ```
+    pub fn warn_if_abc_enabled(&mut self) -> Result<bool, Errors> {
+        match self.read_auto_calibration()? {
+            AutoCalibrationState::Enable => { Ok(true) }
+            AutoCalibrationState::Disable => { Ok(false) }
+        }
+    }
+
+    ///
+    /// Send any command and read 9 byte response as is, no resync on start byte.
+    /// `trace` is called with index and value of every received byte as it arrives,
+    /// so partial and malformed replies can be inspected even when start byte or
+    /// checksum check fails afterwards. For research of undocumented commands.
+    ///
+    /// Available with the `experimental` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let result = mhz.raw_transact_traced(0xA0, [0; 5], |index, byte| {
+    ///     log!("{}: {:02X}", index, byte);
+    /// });
+    /// ```
+    #[cfg(feature = "experimental")]
+    pub fn raw_transact_traced<TraceType>(
+        &mut self,
+        cmd: u8,
+        data: [u8; 5],
+        mut trace: TraceType
+    ) -> Result<[u8; FRAME_LEN], Errors>
+        where
+            TraceType: FnMut(usize, u8)
+    {
+        self.command(cmd, data)?;
+        self.turnaround();
+
+        for index in 0..BUFFER_SIZE {
+            match nb::block!(self.serial.read_byte()) {
+                Ok(byte) => {
+                    trace(index, byte);
+                    self.buffer[index] = byte;
+                }
+                Err(error) => { return self.fail(error); }
+            }
+        }
+
+        if self.buffer[0] != self.protocol.start_byte {
+            return self.fail(Errors::StartByte);
+        }
+
+        if !self.checksum_ok() {
+            return self.fail(Errors::Checksum);
+        }
+
+        Ok(self.buffer)
+    }
+
+    ///
+    /// Send any command and read `frames` consecutive 9 byte response frames (up to 4),
+    /// for firmware which answers some queries with several frames. Each frame is checked
+    /// (start byte, checksum) as it's received, first bad one fails the whole call.
+    /// More than 4 frames (`MAX_RESPONSE_FRAMES`) returns `Errors::Unsupported` without
+    /// sending anything.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let frames = mhz.raw_transact_multi(0xA0, [0; 5], 2).unwrap();
+    /// ```
+    pub fn raw_transact_multi(
+        &mut self,
+        cmd: u8,
+        data: [u8; 5],
+        frames: usize
+    ) -> Result<heapless::Vec<[u8; FRAME_LEN], consts::U4>, Errors> {
+        let mut result: heapless::Vec<[u8; FRAME_LEN], consts::U4> = heapless::Vec::new();
+
+        if frames > result.capacity() {
+            return self.fail(Errors::Unsupported);
+        }
+
+        self.command(cmd, data)?;
+        self.turnaround();
+
+        for _ in 0..frames {
+            self.response()?;
+
+            // capacity is checked above
+            let _ = result.push(self.buffer);
+        }
+
+        Ok(result)
+    }
+
+    ///
+    /// Calibrate zero point and check that it was accepted: after 5 seconds
+    /// concentration must be within `tolerance` of `expected_ppm` (fresh air value the
+    /// sensor is calibrated to, normally 400 ppm), `Errors::CalibrationFailed` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut delay = Delay::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// mhz.calibrate_zero_verified(&mut delay, 400, 50).unwrap();
+    /// ```
+    pub fn calibrate_zero_verified<WaitType>(
+        &mut self,
+        delay: &mut WaitType,
+        expected_ppm: u16,
+        tolerance: u16
+    ) -> Result<(), Errors>
+        where
+            WaitType: DelayMs<u16>
+    {
+        self.calibrate_zero()?;
+
+        delay.delay_ms(CALIBRATION_VERIFY_MS);
+
+        let co2 = self.co2()?;
+
+        if co2.abs_diff(expected_ppm) > tolerance {
+            return self.fail(Errors::CalibrationFailed);
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Run `f` with auto calibration (ABC) disabled, e.g. for manual span calibration,
+    /// and restore previous ABC state afterwards even if `f` fails. Models which can't
+    /// read ABC state back (MH-Z19C) are assumed to have it enabled, as shipped.
+    /// Error of `f` takes precedence over error of restoring.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// mhz.with_abc_disabled(|mhz| mhz.calibrate_span(2000)).unwrap();
+    /// ```
+    pub fn with_abc_disabled<FunctionType, ResultType>(
+        &mut self,
+        f: FunctionType
+    ) -> Result<ResultType, Errors>
+        where
+            FunctionType: FnOnce(&mut Self) -> Result<ResultType, Errors>
+    {
+        let previous = if self.supports(Command::ReadAutoCalibration) {
+            self.read_auto_calibration()?
+        } else {
+            AutoCalibrationState::Enable
+        };
+
+        self.auto_calibration(AutoCalibrationState::Disable)?;
+
+        let result = f(self);
+        let restored = self.auto_calibration(previous);
+
+        let value = result?;
+        restored?;
+
+        Ok(value)
+    }
+
+    ///
+    /// Make sure sensor uses `desired` range: read it back and send range command only
+    /// if it differs. Returns `true` if range was changed. Idempotent, so it can run on
+    /// every start to heal sensor resets. Models without range read back (MH-Z19C)
+    /// return `Errors::Unsupported`, use `range()` for them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// if mhz.ensure_range(Range::_2000).unwrap() {
+    ///     // range was reset
+    /// }
+    /// ```
+    pub fn ensure_range(&mut self, desired: Range) -> Result<bool, Errors> {
+        if !self.supports(Command::ReadRange) {
+            return self.fail(Errors::Unsupported);
+        }
+
+        if self.read_range()? == desired {
+            return Ok(false);
+        }
+
+        self.range(desired)?;
+
+        Ok(true)
+    }
+
+    ///
+    /// Read raw (pre-conversion) sensor value behind concentration, e.g. for custom
+    /// calibration curves. Command 0x84 is not in datasheet; value is taken from bytes
+    /// 2-3 of response as observed on MH-Z19B (community findings), its scale is
+    /// unitless and differs between units. Other models return `Errors::Unsupported`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let raw: u16 = mhz.raw_adc().unwrap();
+    /// ```
+    pub fn raw_adc(&mut self) -> Result<u16, Errors> {
+        if !self.supports(Command::ReadRawConcentration) {
+            return self.fail(Errors::Unsupported);
+        }
+
+        self.transact(Command::ReadRawConcentration as u8, [0; 5])?;
+
+        Ok(decode_u16(self.buffer[2], self.buffer[3]))
+    }
+
+    ///
+    /// Read firmware version as 4 ASCII characters (e.g. `b"0443"`). Command 0xA0 is
+    /// not in datasheet but known to work on MH-Z19B, other models return
+    /// `Errors::Unsupported`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let version: [u8; 4] = mhz.firmware_version().unwrap();
+    /// ```
+    pub fn firmware_version(&mut self) -> Result<[u8; 4], Errors> {
+        if !self.supports(Command::ReadFirmwareVersion) {
+            return self.fail(Errors::Unsupported);
+        }
+
+        self.transact(Command::ReadFirmwareVersion as u8, [0; 5])?;
+
+        Ok([self.buffer[2], self.buffer[3], self.buffer[4], self.buffer[5]])
+    }
+
+    ///
+    /// Query everything sensor can report (firmware version, range, ABC state, current
+    /// reading) in one call, e.g. for "sensor info" command. Queries the model doesn't
+    /// support are `None` instead of failing, errors of supported ones are returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let info = mhz.info().unwrap();
+    ///
+    /// if let Some(range) = info.range {
+    ///     // show range
+    /// }
+    /// ```
+    pub fn info(&mut self) -> Result<SensorInfo, Errors> {
+        let firmware_version = if self.supports(Command::ReadFirmwareVersion) {
+            Some(self.firmware_version()?)
+        } else {
+            None
+        };
+
+        let range = if self.supports(Command::ReadRange) {
+            Some(self.read_range()?)
+        } else {
+            None
+        };
+
+        let abc = if self.supports(Command::ReadAutoCalibration) {
+            Some(self.read_auto_calibration()?)
+        } else {
+            None
+        };
+
+        Ok(SensorInfo {
+            firmware_version,
+            range,
+            abc,
+            reading: self.measure()?
+        })
+    }
+
+    ///
+    /// Reset sensor settings (range, ABC) to factory defaults and wait 5 seconds for it
+    /// to reinitialize. Command 0x78 is not in datasheet but known to work on MH-Z19B,
+    /// other models return `Errors::Unsupported`. Cached range is reset to factory one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut delay = Delay::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// mhz.factory_reset(&mut delay).unwrap();
+    /// ```
+    pub fn factory_reset<WaitType>(&mut self, delay: &mut WaitType) -> Result<(), Errors>
+        where
+            WaitType: DelayMs<u16>
+    {
+        if !self.supports(Command::FactoryReset) {
+            return self.fail(Errors::Unsupported);
+        }
+
+        self.write_command(Command::FactoryReset as u8, [0; 5])?;
+
+        delay.delay_ms(FACTORY_RESET_MS);
+
+        self.range = DEFAULT_RANGE;
+        self.last_accepted = None;
+
+        Ok(())
+    }
+
+    ///
+    /// Apply all settings of `config`. Measurement mode goes first, so a mode the
+    /// model doesn't support fails before anything is sent, then range and ABC
+    /// (each followed by configured settle time). Stops at first error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let config = SensorConfig {
+    ///     range: Range::_2000,
+    ///     abc: AutoCalibrationState::Disable,
+    ///     measurement_mode: None
+    /// };
+    ///
+    /// mhz.apply_config(&config).unwrap();
+    /// ```
+    pub fn apply_config(&mut self, config: &SensorConfig) -> Result<(), Errors> {
+        if let Some(mode) = config.measurement_mode {
+            self.set_measurement_mode(mode)?;
+        }
+
+        self.range(config.range)?;
+        self.auto_calibration(config.abc)
+    }
+
+    ///
+    /// Read back configuration from sensor and update cached state (range used by
+    /// `full_scale()`), e.g. after brown-out reset the sensor to factory settings
+    /// behind driver's back. Compare result with applied config to detect it. Needs
+    /// range and ABC read back, other models return `Errors::Unsupported`. No supported
+    /// model reports measurement mode, so `measurement_mode` is `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::with_config(serial, config).unwrap();
+    ///
+    /// // after supply glitch
+    /// let actual = mhz.resync_config().unwrap();
+    ///
+    /// if actual.range != config.range || actual.abc != config.abc {
+    ///     mhz.apply_config(&config).unwrap();
+    /// }
+    /// ```
+    pub fn resync_config(&mut self) -> Result<SensorConfig, Errors> {
+        if !self.supports(Command::ReadRange) || !self.supports(Command::ReadAutoCalibration) {
+            return self.fail(Errors::Unsupported);
+        }
+
+        Ok(SensorConfig {
+            range: self.read_range()?,
+            abc: self.read_auto_calibration()?,
+            measurement_mode: None
+        })
+    }
+}
+
+impl<SerialType, DelayType> Mhz19Trait for Mhz19<SerialType, DelayType>
+    where
+        SerialType: Write<u8> + ReadFrame,
+        DelayType: DelayMs<u16>
+{
+    type Error = Errors;
+
+    /// Get gas concentration from mhz-19
+    fn co2(&mut self) -> Result<u16, Self::Error> {
+        Ok(self.measure()?.co2)
+    }
+
+    ///
+    /// Get gas concentration, temperature and status byte from mhz-19
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let reading: Reading = mhz.measure().unwrap();
+    /// ```
+    fn measure(&mut self) -> Result<Reading, Self::Error> {
+        self.check_ready()?;
+
+        self.measure_ungated()
+    }
+
+    ///
+    /// Calibrate zero point: current concentration is taken as 400 ppm.
+    /// Sensor must be in fresh air for at least 20 minutes before.
+    /// Sensor sends no response for this command.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// mhz.calibrate_zero().unwrap();
+    /// ```
+    fn calibrate_zero(&mut self) -> Result<(), Self::Error> {
+        self.write_command(Command::CalibrateZeroPoint as u8, [0; 5])
+    }
+
+    ///
+    /// Calibrate span point: current concentration is taken as `ppm`.
+    /// Zero point calibration must be done before.
+    /// Sensor sends no response for this command.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// mhz.calibrate_span(2000).unwrap();
+    /// ```
+    fn calibrate_span(&mut self, ppm: u16) -> Result<(), Self::Error> {
+        let ppm = ppm.to_be_bytes();
+
+        self.write_command(Command::CalibrateSpanPoint as u8, [ppm[0], ppm[1], 0, 0, 0])
+    }
+
+    ///
+    /// Set auto calibration or not for mhz-19 driver
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// mhz.auto_calibration(AutoCalibrationState::Disable).unwrap();
+    /// ```
+    ///
+    fn auto_calibration(&mut self, state: AutoCalibrationState) -> Result<(), Self::Error> {
+        let state_byte: u8 = match state {
+            AutoCalibrationState::Enable => { 0xA0 }
+            AutoCalibrationState::Disable => { 0x00 }
+        };
+
+        let data: [u8; 5] = [state_byte, 0, 0, 0, 0];
+
+        self.write_command(Command::AutoCalibration as u8, data)
+    }
+
+    /// Read auto calibration state from mhz-19
+    fn read_auto_calibration(&mut self) -> Result<AutoCalibrationState, Self::Error> {
+        let data: [u8; 5] = [0; 5];
+
+        self.transact(Command::ReadAutoCalibration as u8, data)?;
+
+        match self.buffer[7] {
+            0 => { Ok(AutoCalibrationState::Disable) }
+            _ => { Ok(AutoCalibrationState::Enable) }
+        }
+    }
+
+    ///
+    /// Set maximum range for mhz-19 conversation (from 0 to range value)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// mhz.range(Range::_2000).unwrap();
+    /// ```
+    fn range(&mut self, range: Range) -> Result<(), Self::Error> {
+        self.write_command(Command::SetRange as u8, range.data())?;
+        self.range = range;
+
+        Ok(())
+    }
+
+    ///
+    /// Read configured range from mhz-19. Returns `Errors::InvalidResponse`
+    /// if sensor reports value which is not one of `Range` variants.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// let range: Range = mhz.read_range().unwrap();
+    /// ```
+    fn read_range(&mut self) -> Result<Range, Self::Error> {
+        let data: [u8; 5] = [0; 5];
+
+        self.transact(Command::ReadRange as u8, data)?;
+
+        let range = match decode_u16(self.buffer[4], self.buffer[5]) {
+            1000 => { Range::_1000 }
+            2000 => { Range::_2000 }
+            3000 => { Range::_3000 }
+            5000 => { Range::_5000 }
+            10000 => { Range::_10000 }
+            _ => { return self.fail(Errors::InvalidResponse); }
+        };
+
+        self.range = range;
+
+        Ok(range)
+    }
+
+    ///
+    /// Check if configured model supports command, so portable code can avoid
+    /// sending commands which hang or misbehave
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let serial = Serial::new(...);
+    /// let mut mhz = Mhz19::new(serial);
+    ///
+    /// if mhz.supports(Command::ReadRange) {
+    ///     let range = mhz.read_range().unwrap();
+    /// }
+    /// ```
+    fn supports(&self, cmd: Command) -> bool {
+        self.model.commands().contains(&cmd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapless::{Vec, consts};
+
+    struct DummySerial<'a> {
+        input: &'a mut Vec<u8, consts::U64>,
+        output: &'a mut Vec<u8, consts::U64>
+    }
+
+    impl<'a> DummySerial<'a> {
+        fn new(
+            input: &'a mut Vec<u8, consts::U64>,
+            output: &'a mut Vec<u8, consts::U64>
+        ) -> Self
+        {
+            Self {
+                input,
+                output
+            }
+        }
+    }
+
+    impl<'a> Write<u8> for DummySerial<'a> {
+        type Error = nb::Error<()>;
+
+        fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+            self.input.push(word).unwrap();
+
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), Self::Error> { Ok(()) }
+    }
+
+    impl<'a> Read<u8> for DummySerial<'a> {
+        type Error = ();
+
+        // Empty output means line is dead, so fail instead of blocking forever
+        fn read(&mut self) -> nb::Result<u8, Self::Error> {
+            if self.output.is_empty() {
+                return Err(nb::Error::Other(()));
+            }
+
+            self.output.rotate_left(1);
+
+            match self.output.pop() {
+                Some(data) => { Ok(data) }
+                _ => { Err(nb::Error::Other(())) }
+            }
+        }
+    }
+
+    #[cfg(feature = "read-buf")]
+    impl<'a> ReadBuf for DummySerial<'a> {
+        type Error = ();
+
+        fn read_buf(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error> {
+            if self.output.len() < buffer.len() {
+                return Err(nb::Error::Other(()));
+            }
+
+            for data in buffer.iter_mut() {
+                *data = self.read()?;
+            }
+
+            Ok(())
+        }
+    }
+
+    // Frame from first 8 bytes with MH-Z19 checksum appended
+    fn packet(data: [u8; 8]) -> [u8; 9] {
+        let mut packet: [u8; 9] = [0; 9];
+
+        packet[..8].copy_from_slice(&data);
+        packet[8] = checksum(&data);
+        packet
+    }
+
+    // Read concentration response with `co2` and raw temperature byte
+    fn reading_frame(co2: u16, temperature: u8) -> [u8; 9] {
+        packet([0xFF, 0x86, (co2 >> 8) as u8, co2 as u8, temperature, 0, 0, 0])
+    }
+
+    // Read concentration response of clone which sends `address` before echo
+    fn shifted_frame(address: u8, co2: u16) -> [u8; 9] {
+        packet([0xFF, address, 0x86, (co2 >> 8) as u8, co2 as u8, 0x40, 0, 0])
+    }
+
+    // Serial output with `frames` in order of reception
+    fn replies(frames: &[[u8; 9]]) -> Vec<u8, consts::U64> {
+        let mut output: Vec<u8, consts::U64> = Vec::new();
+
+        for frame in frames.iter() {
+            output.extend_from_slice(frame).unwrap();
+        }
+
+        output
+    }
+
+    #[test]
+    fn test_checksum() {
+        // example data from datasheet
+        let test_data_1: [u8; 9] = [0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79];
+        let test_data_2: [u8; 9] = [0xFF, 0x01, 0x87, 0x00, 0x00, 0x00, 0x00, 0x00, 0x78];
+        let test_data_3: [u8; 9] = [0xFF, 0x01, 0x88, 0x07, 0xD0, 0x00, 0x00, 0x00, 0xA0];
+
+        assert_eq!(checksum(&test_data_1[0..8]), test_data_1[8]);
+        assert_eq!(checksum(&test_data_2[0..8]), test_data_2[8]);
+        assert_eq!(checksum(&test_data_3[0..8]), test_data_3[8]);
+    }
+
+    #[test]
+    fn command_test() {
+        let mut input: Vec<u8, consts::U64> = Vec::new();
+        let mut _output: Vec<u8, consts::U64> = Vec::new();
+
+        let serial = DummySerial::new(&mut input, &mut _output);
+
+        let mut mhz = Mhz19::new(serial);
+
+        match mhz.command(0x86, [0_u8; 5]) {
+            Ok(_) => {}
+            _ => { panic!("Error write command"); }
+        }
+
+        let expected_answer: [u8; 9] = [
+            0xFF,
             0x01,
             0x86,
             0x00,
@@ -285,39 +3948,1016 @@ mod tests {
             0x79
         ];
 
-        assert_eq!(input, expected_answer);
+        assert_eq!(input, expected_answer);
+    }
+
+    #[test]
+    fn response_test() -> Result<(), &'static str> {
+        let mut _input: Vec<u8, consts::U64> = Vec::new();
+        let mut output: Vec<u8, consts::U64> = Vec::new();
+
+        let mut packet: [u8; 9] = [
+            0xFF,
+            0x01,
+            0x04,
+            0x80,
+            0xB0,
+            0x00,
+            0x00,
+            0x00,
+            0x00,
+        ];
+
+        packet[8] = checksum(&packet[0..8]);
+
+        for &b in packet.iter() {
+            output.push(b).unwrap();
+        }
+
+        let serial = DummySerial::new(&mut _input, &mut output);
+
+        let mut mhz = Mhz19::new(serial);
+
+        match mhz.response() {
+            Ok(_) => { Ok(()) }
+            _ => { Err("Can't read successful response") }
+        }
+    }
+
+    #[test]
+    fn protocol_test() {
+        let mut input = Vec::new();
+        let mut output = replies(&[packet([0x42, 0x86, 0x04, 0xB0, 0x00, 0x00, 0x00, 0x00])]);
+
+        let protocol = Protocol { start_byte: 0x42, address: 0x02 };
+
+        {
+            let serial = DummySerial::new(&mut input, &mut output);
+            let mut mhz = Mhz19::with_protocol(serial, protocol);
+
+            assert!(mhz.command(0x86, [0_u8; 5]).is_ok());
+            assert!(mhz.response().is_ok());
+        }
+
+        assert_eq!(input[0..3], [0x42, 0x02, 0x86]);
+    }
+
+    #[test]
+    fn checksum_variant_test() {
+        let frame: [u8; 8] = [0xFF, 0x86, 0x04, 0xB0, 0x40, 0x00, 0x00, 0x00];
+
+        assert_eq!(StandardChecksum.checksum(&frame), checksum(&frame));
+        assert_eq!(XorChecksum.checksum(&frame), 0x86 ^ 0x04 ^ 0xB0 ^ 0x40);
+
+        let mut xor_frame = reading_frame(1200, 0x40);
+        xor_frame[8] = XorChecksum.checksum(&xor_frame[0..8]);
+
+        let mut input = Vec::new();
+        let mut output = replies(&[xor_frame]);
+
+        {
+            let serial = DummySerial::new(&mut input, &mut output);
+            let mut mhz = Mhz19::builder(serial).checksum(&XorChecksum).build();
+
+            assert!(mhz.command(0x86, [0_u8; 5]).is_ok());
+            assert!(mhz.response().is_ok());
+        }
+
+        assert_eq!(input[8], 0x01 ^ 0x86);
+
+        // XOR frame is rejected by standard driver
+        let mut input = Vec::new();
+        let mut output = replies(&[xor_frame]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.co2(), Err(Errors::Checksum));
+    }
+
+    #[test]
+    fn start_byte_test() {
+        // valid MH-Z19 frames, but driver expects 0x42
+        let mut input = Vec::new();
+        let mut output = replies(&[reading_frame(1200, 0x00); 3]);
+
+        let protocol = Protocol { start_byte: 0x42, address: 0x01 };
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::with_protocol(serial, protocol);
+
+        assert_eq!(mhz.response(), Err(Errors::StartByte));
+    }
+
+    #[test]
+    fn stuffed_start_byte_test() {
+        let reading = reading_frame(1200, 0x40);
+
+        let mut input = Vec::new();
+        let mut output: Vec<u8, consts::U64> = Vec::new();
+
+        output.extend_from_slice(&[0xFF, 0xFF]).unwrap();
+        output.extend_from_slice(&reading).unwrap();
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.response(), Ok(()));
+        assert_eq!(mhz.buffer, reading);
+        assert_eq!(decode_reading(&mhz.buffer, &DecodeVariant::STANDARD, DEFAULT_FLOOR_PPM, DEFAULT_RANGE.ppm()).co2, 1200);
+    }
+
+    #[test]
+    fn warmup_remaining_test() {
+        let mut input = Vec::new();
+        let mut output = Vec::new();
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.warmup_remaining_ms(0), 180_000);
+        assert_eq!(mhz.warmup_remaining_ms(30_000), 150_000);
+
+        mhz.set_model(Model::C);
+
+        assert_eq!(mhz.warmup_remaining_ms(30_000), 30_000);
+        assert_eq!(mhz.warmup_remaining_ms(90_000), 0);
+    }
+
+    #[test]
+    fn implausible_temperature_test() {
+        let mut frame: [u8; 9] = [0xFF, 0x86, 0x04, 0xB0, 0x40, 0x00, 0x00, 0x00, 0x00];
+
+        assert_eq!(decode_reading(&frame, &DecodeVariant::STANDARD, DEFAULT_FLOOR_PPM, DEFAULT_RANGE.ppm()).temperature, Some(24));
+
+        // 250 - 40 = 210 °C
+        frame[4] = 250;
+
+        let reading = decode_reading(&frame, &DecodeVariant::STANDARD, DEFAULT_FLOOR_PPM, DEFAULT_RANGE.ppm());
+
+        assert_eq!(reading.co2, 1200);
+        assert_eq!(reading.temperature, None);
+    }
+
+    #[test]
+    fn temperature_byte_exhaustive_test() {
+        let mut frame: [u8; 9] = [0xFF, 0x86, 0x04, 0xB0, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+        for byte in 0..=u8::MAX {
+            frame[4] = byte;
+
+            let reading = decode_reading(&frame, &DecodeVariant::STANDARD, DEFAULT_FLOOR_PPM, DEFAULT_RANGE.ppm());
+
+            if let Some(temperature) = reading.temperature {
+                assert!((TEMPERATURE_MIN..=TEMPERATURE_MAX).contains(&temperature));
+            }
+
+            assert!((-40..=215).contains(&raw_temperature(byte)));
+            assert_eq!(decode_log_record(&[0, 0, 0, 0, 0, 0, byte, 0]).2, raw_temperature(byte));
+        }
+
+        // custom variant pointing outside of frame
+        let variant = DecodeVariant { co2: usize::MAX, temperature: 9, status: 100 };
+        let reading = decode_reading(&frame, &variant, DEFAULT_FLOOR_PPM, DEFAULT_RANGE.ppm());
+
+        assert_eq!(reading.co2, 0);
+        assert_eq!(reading.temperature, Some(-40));
+        assert_eq!(reading.status, 0);
+    }
+
+    #[test]
+    fn supports_test() {
+        let mut input = Vec::new();
+        let mut output = Vec::new();
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert!(mhz.supports(Command::ReadConcentration));
+        assert!(mhz.supports(Command::ReadRawConcentration));
+        assert!(mhz.supports(Command::ReadRange));
+        assert!(mhz.supports(Command::ReadAutoCalibration));
+        assert!(mhz.supports(Command::FactoryReset));
+
+        mhz.set_model(Model::C);
+
+        assert!(mhz.supports(Command::ReadConcentration));
+        assert!(mhz.supports(Command::SetRange));
+        assert!(!mhz.supports(Command::ReadRawConcentration));
+        assert!(!mhz.supports(Command::ReadRange));
+        assert!(!mhz.supports(Command::ReadAutoCalibration));
+        assert!(!mhz.supports(Command::FactoryReset));
+    }
+
+    #[test]
+    fn co2_lenient_test() {
+        let reading = reading_frame(1200, 0x40);
+
+        // same frame with corrupted checksum
+        let mut corrupted = reading;
+        corrupted[8] = corrupted[8].wrapping_add(1);
+
+        let mut input = Vec::new();
+        let mut output = replies(&[reading, corrupted]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.co2_lenient(), Ok((1200, true)));
+        assert_eq!(mhz.co2_lenient(), Ok((1200, false)));
+    }
+
+    #[test]
+    fn decode_test() {
+        let mut frame = reading_frame(1200, 0x40);
+
+        let expected = Reading { co2: 1200, temperature: Some(24), status: 0, below_floor: false, saturated: false };
+
+        assert_eq!(Reading::decode(frame), Ok(expected));
+
+        frame[3] = 0xB1;
+
+        assert_eq!(Reading::decode(frame), Err(Errors::Checksum));
+
+        frame[0] = 0x42;
+
+        assert_eq!(Reading::decode(frame), Err(Errors::StartByte));
+    }
+
+    #[test]
+    fn decode_u16_test() {
+        assert_eq!(decode_u16(0x04, 0xB0), 1200);
+        assert_eq!(decode_u16_le(0xB0, 0x04), 1200);
+        assert_eq!(decode_u16(0xFF, 0xFF), u16::MAX);
+        assert_eq!(decode_u16_le(0x01, 0x00), 1);
+
+        for &(a, b) in [(0x00, 0x00), (0x12, 0x34), (0xFF, 0x01)].iter() {
+            assert_eq!(decode_u16(a, b), u16::from_be_bytes([a, b]));
+            assert_eq!(decode_u16_le(a, b), u16::from_le_bytes([a, b]));
+        }
+    }
+
+    #[test]
+    fn decode_identifier_test() {
+        let frame = packet([0xFF, 0xA0, b' ', b'Z', b'1', b'9', b'-', b'7']);
+
+        assert_eq!(decode_identifier(&frame[2..8]).unwrap().as_str(), "Z19-7");
+        assert_eq!(decode_identifier(b"SN 0042\0\0\xFF").unwrap().as_str(), "SN 0042");
+        assert_eq!(decode_identifier(&[0x00, 0xFF, b' ']).unwrap().as_str(), "");
+        assert_eq!(decode_identifier(&[]).unwrap().as_str(), "");
+        assert_eq!(decode_identifier(b"0123456789ABCDEF").unwrap().len(), 16);
+        assert_eq!(decode_identifier(b"0123456789ABCDEFG"), Err(Errors::InvalidResponse));
+        assert_eq!(decode_identifier(&[b'A', 0x07, b'B']), Err(Errors::InvalidResponse));
+    }
+
+    #[cfg(feature = "experimental")]
+    #[test]
+    fn serial_number_test() {
+        let mut input = Vec::new();
+        let mut output = Vec::new();
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.serial_number(), Err(Errors::Unsupported));
+    }
+
+    #[test]
+    fn decode_first_frame_test() {
+        let frame = reading_frame(1200, 0x40);
+
+        let mut data: [u8; 16] = [0; 16];
+
+        // noise with start bytes, then frame and tail of next one
+        data[..4].copy_from_slice(&[0xFF, 0x86, 0xFF, 0x13]);
+        data[4..13].copy_from_slice(&frame);
+        data[13..].copy_from_slice(&[0xFF, 0x86, 0x04]);
+
+        let (offset, reading) = decode_first_frame(&data).unwrap();
+
+        assert_eq!(offset, 4);
+        assert_eq!(reading.co2, 1200);
+
+        assert_eq!(decode_first_frame(&frame).map(|(offset, _)| offset), Some(0));
+
+        // stuffed start byte and response to other command before reading
+        let range = packet([0xFF, 0x9B, 0x00, 0x00, 0x13, 0x88, 0x00, 0x00]);
+
+        let mut mixed: [u8; 19] = [0xFF; 19];
+
+        mixed[..9].copy_from_slice(&range);
+        mixed[10..].copy_from_slice(&frame);
+
+        assert_eq!(decode_first_frame(&mixed).map(|(offset, _)| offset), Some(10));
+        assert_eq!(decode_first_frame(&data[5..]), None);
+        assert_eq!(decode_first_frame(&frame[..8]), None);
+        assert_eq!(decode_first_frame(&[]), None);
+    }
+
+    #[test]
+    fn frame_parser_test() {
+        let frame = reading_frame(1200, 0x40);
+
+        let mut corrupted = frame;
+        corrupted[3] = 0x00;
+
+        let mut parser = FrameParser::new(START_BYTE);
+        let mut frames: usize = 0;
+
+        for &b in [0x00, 0x12, 0xFF, 0xFF].iter().chain(corrupted.iter()).chain(frame.iter()) {
+            if let Some(received) = parser.push(b) {
+                assert_eq!(received, frame);
+                frames += 1;
+            }
+        }
+
+        assert_eq!(frames, 1);
+    }
+
+    #[test]
+    fn frame_parser_random_stream_test() {
+        let mut parser = FrameParser::new(START_BYTE);
+        let mut seed: u32 = 0x1234_5678;
+
+        for _ in 0..100_000 {
+            // LCG, biased to start bytes to hit resync paths
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+
+            let byte = if seed >> 29 == 0 { START_BYTE } else { (seed >> 16) as u8 };
+
+            if let Some(frame) = parser.push(byte) {
+                assert_eq!(frame[0], START_BYTE);
+                assert_ne!(frame[1], START_BYTE);
+                assert!(frame_checksum_ok(&StandardChecksum, &frame));
+            }
+        }
+    }
+
+    #[test]
+    fn interrupt_transaction_test() {
+        let mut input = Vec::new();
+        let mut output = Vec::new();
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        mhz.start_measure();
+
+        let mut tx: [u8; 9] = [0; 9];
+
+        for b in tx.iter_mut() {
+            *b = mhz.next_tx_byte().unwrap();
+        }
+
+        assert_eq!(mhz.next_tx_byte(), None);
+        assert_eq!(tx, [0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79]);
+
+        let frame = reading_frame(1200, 0x40);
+
+        for &b in frame[0..8].iter() {
+            mhz.feed_byte(b);
+            assert_eq!(mhz.poll_result(), None);
+        }
+
+        mhz.feed_byte(frame[8]);
+
+        assert_eq!(mhz.poll_result().map(|result| result.map(|reading| reading.co2)), Some(Ok(1200)));
+        assert_eq!(mhz.poll_result(), None);
+
+        // other command and implausible value are reported, not decoded
+        let frame = packet([0xFF, 0x9B, 0x00, 0x00, 0x13, 0x88, 0x00, 0x00]);
+
+        frame.iter().for_each(|&b| mhz.feed_byte(b));
+
+        assert_eq!(mhz.poll_result(), Some(Err(Errors::UnexpectedCommand { expected: 0x86, got: 0x9B })));
+
+        reading_frame(0xFF00, 0x40).iter().for_each(|&b| mhz.feed_byte(b));
+
+        assert_eq!(mhz.poll_result(), Some(Err(Errors::ImplausibleValue)));
+    }
+
+    #[test]
+    fn accumulator_saturation_test() {
+        let mut accumulator = Accumulator::default();
+
+        assert_eq!(accumulator.mean(), None);
+
+        for _ in 0..1_000_000 {
+            accumulator.push(u16::MAX);
+        }
+
+        assert_eq!(accumulator.mean(), Some(u16::MAX));
+
+        accumulator.sum = u64::MAX;
+        accumulator.count = u32::MAX;
+        accumulator.push(u16::MAX);
+
+        assert_eq!(accumulator.count, u32::MAX);
+        assert_eq!(accumulator.mean(), Some(u16::MAX));
+    }
+
+    #[test]
+    fn diagnose_test() {
+        fn diagnose(frames: &[u8]) -> Diagnosis {
+            let mut input = Vec::new();
+            let mut output: Vec<u8, consts::U64> = Vec::new();
+
+            output.extend_from_slice(frames).unwrap();
+
+            Mhz19::new(DummySerial::new(&mut input, &mut output)).diagnose()
+        }
+
+        let reading = reading_frame(1200, 0x40);
+
+        let mut corrupted = reading;
+        corrupted[8] = corrupted[8].wrapping_add(1);
+
+        let mut frames: [u8; 45] = [0; 45];
+
+        assert_eq!(diagnose(&[]), Diagnosis::NoResponse);
+
+        for index in 0..5 {
+            frames[index * 9..(index + 1) * 9].copy_from_slice(&reading);
+        }
+
+        assert_eq!(diagnose(&frames), Diagnosis::Ok);
+
+        frames[9..18].copy_from_slice(&corrupted);
+
+        assert_eq!(diagnose(&frames), Diagnosis::Noisy);
+
+        // garbage as seen at wrong baudrate
+        let mut garbage: [u8; 60] = [0; 60];
+
+        for (index, b) in garbage.iter_mut().enumerate() {
+            *b = 0x80 | index as u8;
+        }
+
+        assert_eq!(diagnose(&garbage), Diagnosis::BaudMismatch);
     }
 
     #[test]
-    fn response_test() -> Result<(), &'static str> {
-        let mut _input: Vec<u8, consts::U9> = Vec::new();
-        let mut output: Vec<u8, consts::U9> = Vec::new();
+    fn decode_variant_test() {
+        let standard: [u8; 9] = [0xFF, 0x86, 0x04, 0xB0, 0x40, 0x05, 0x00, 0x00, 0x00];
+        let shifted: [u8; 9] = [0xFF, 0x01, 0x86, 0x04, 0xB0, 0x40, 0x05, 0x00, 0x00];
 
-        let mut packet: [u8; 9] = [
-            0xFF,
-            0x01,
-            0x04,
-            0x80,
-            0xB0,
-            0x00,
-            0x00,
-            0x00,
-            0x00,
-        ];
+        let expected = Reading { co2: 1200, temperature: Some(24), status: 5, below_floor: false, saturated: false };
 
-        packet[8] = checksum(&packet[0..8]);
+        assert_eq!(decode_reading(&standard, &DecodeVariant::STANDARD, DEFAULT_FLOOR_PPM, DEFAULT_RANGE.ppm()), expected);
+        assert_eq!(decode_reading(&shifted, &DecodeVariant::SHIFTED, DEFAULT_FLOOR_PPM, DEFAULT_RANGE.ppm()), expected);
 
-        for &b in packet.iter() {
-            output.push(b).unwrap();
+        let mut input = Vec::new();
+        let mut output = replies(&[packet([0xFF, 0x01, 0x86, 0x04, 0xB0, 0x40, 0x05, 0x00])]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::builder(serial).decode_variant(DecodeVariant::SHIFTED).build();
+
+        assert_eq!(mhz.measure(), Ok(expected));
+    }
+
+    #[test]
+    fn address_filter_test() {
+        // sensors 0x02 and 0x01 answer on the same bus
+        let mut input = Vec::new();
+        let mut output = replies(&[shifted_frame(0x02, 1000), shifted_frame(0x01, 1200), shifted_frame(0x02, 1000)]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::builder(serial).decode_variant(DecodeVariant::SHIFTED).build();
+
+        assert_eq!(mhz.set_address_filter(2), Ok(()));
+
+        assert_eq!(mhz.co2(), Ok(1200));
+
+        let mut input = Vec::new();
+        let mut output = replies(&[shifted_frame(0x02, 1000); 3]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::builder(serial).decode_variant(DecodeVariant::SHIFTED).build();
+
+        assert_eq!(mhz.set_address_filter(2), Ok(()));
+
+        assert_eq!(mhz.co2(), Err(Errors::UnexpectedAddress));
+
+        assert_eq!(mhz.set_address_filter(0), Ok(()));
+
+        assert_eq!(mhz.co2(), Ok(1000));
+    }
+
+    #[test]
+    fn address_filter_unverified_test() {
+        // foreign frame with broken checksum, then own one
+        let mut foreign = shifted_frame(0x02, 1000);
+        foreign[8] ^= 0x01;
+
+        let mut input = Vec::new();
+        let mut output = replies(&[foreign, shifted_frame(0x01, 1200), foreign, shifted_frame(0x01, 1200)]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::builder(serial).decode_variant(DecodeVariant::SHIFTED).build();
+
+        assert_eq!(mhz.set_address_filter(3), Ok(()));
+        assert_eq!(mhz.co2_lenient(), Ok((1200, true)));
+        assert_eq!(mhz.measure_with_quality().map(|(reading, _)| reading.co2), Ok(1200));
+
+        let mut input = Vec::new();
+        let mut output = replies(&[shifted_frame(0x02, 1000); 2]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::builder(serial).decode_variant(DecodeVariant::SHIFTED).build();
+
+        assert_eq!(mhz.set_address_filter(2), Ok(()));
+        assert_eq!(mhz.co2_lenient(), Err(Errors::UnexpectedAddress));
+    }
+
+    #[test]
+    fn address_filter_standard_test() {
+        let mut input = Vec::new();
+        let mut output = replies(&[reading_frame(1200, 0x40)]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        // byte 1 of datasheet layout is echo, not address
+        assert_eq!(DecodeVariant::STANDARD.address(), None);
+        assert_eq!(DecodeVariant::SHIFTED.address(), Some(1));
+        assert_eq!(mhz.set_address_filter(2), Err(Errors::InvalidArgument));
+        assert_eq!(mhz.co2(), Ok(1200));
+
+        mhz.set_decode_variant(DecodeVariant::SHIFTED);
+
+        assert_eq!(mhz.set_address_filter(2), Ok(()));
+
+        mhz.set_decode_variant(DecodeVariant::STANDARD);
+
+        assert!(!mhz.foreign_address());
+    }
+
+    #[test]
+    fn address_filter_nb_test() {
+        let mut input = Vec::new();
+        let mut output = replies(&[shifted_frame(0x02, 1000), shifted_frame(0x01, 1200)]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::builder(serial).decode_variant(DecodeVariant::SHIFTED).build();
+
+        assert_eq!(mhz.set_address_filter(2), Ok(()));
+        assert_eq!(nb::block!(mhz.read_co2()), Ok(1200));
+    }
+
+    #[test]
+    fn byte_swap_check_all_paths_test() {
+        // 1200 ppm with swapped bytes
+        let swapped = reading_frame(0xB004, 0x40);
+
+        let mut input = Vec::new();
+        let mut output = replies(&[swapped; 5]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::with_range(serial, Range::_2000).unwrap();
+
+        mhz.set_byte_swap_check(true);
+
+        assert_eq!(mhz.co2_lenient(), Err(Errors::PossibleByteSwap));
+        assert_eq!(mhz.measure_with_quality(), Err(Errors::PossibleByteSwap));
+        assert_eq!(mhz.listen(), Err(Errors::PossibleByteSwap));
+        assert_eq!(nb::block!(mhz.read_co2()), Err(Errors::PossibleByteSwap));
+
+        swapped.iter().for_each(|&b| mhz.feed_byte(b));
+
+        assert_eq!(mhz.poll_result(), Some(Err(Errors::PossibleByteSwap)));
+    }
+
+    #[test]
+    fn read_co2_shifted_test() {
+        let mut input = Vec::new();
+        let mut output = replies(&[shifted_frame(0x01, 1200)]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::builder(serial).decode_variant(DecodeVariant::SHIFTED).build();
+
+        assert_eq!(nb::block!(mhz.read_co2()), Ok(1200));
+
+        // stale answer to other command is reported like in blocking path
+        let mut input = Vec::new();
+        let mut output = replies(&[packet([0xFF, 0x01, 0x9B, 0x00, 0x00, 0x13, 0x88, 0x00])]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::builder(serial).decode_variant(DecodeVariant::SHIFTED).build();
+
+        assert_eq!(
+            nb::block!(mhz.read_co2()),
+            Err(Errors::UnexpectedCommand { expected: 0x86, got: 0x9B })
+        );
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn dew_point_test() {
+        for &x in [0.01_f32, 0.5, 1.0, 1.5, 2.0, 10.0, 100.0].iter() {
+            assert!((ln(x) - x.ln()).abs() < 1e-5);
         }
 
-        let serial = DummySerial::new(&mut _input, &mut output);
+        assert!((dew_point_c(20, 50.0) - 9.26).abs() < 0.01);
+        assert!((dew_point_c(25, 100.0) - 25.0).abs() < 0.01);
+        assert!((dew_point_c(25, 150.0) - 25.0).abs() < 0.01);
+        assert!(dew_point_c(25, 0.0).is_nan());
+        assert!(dew_point_c(25, f32::NAN).is_nan());
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn estimate_occupancy_test() {
+        assert!((estimate_occupancy(1000, 60.0, 1.5) - 3.0).abs() < 0.01);
+        assert!((estimate_occupancy(400, 60.0, 1.5)).abs() < 0.01);
+        assert_eq!(estimate_occupancy(350, 60.0, 1.5), 0.0);
+        assert_eq!(estimate_occupancy(1000, f32::NAN, 1.5), 0.0);
+    }
 
+    #[test]
+    fn status_flags_test() {
+        let flags = StatusFlags::from_byte(0x40);
+
+        assert!(flags.is_stable());
+        assert!(!flags.has_error());
+
+        let flags = StatusFlags::from_byte(0xFF);
+
+        assert!(flags.is_stable());
+        assert!(flags.has_error());
+        assert!(flags.contains(0x82));
+        assert_eq!(flags.bits(), 0xFF);
+
+        let reading = Reading { co2: 1200, temperature: None, status: 0x01, below_floor: false, saturated: false };
+
+        assert!(reading.flags().has_error());
+        assert!(!reading.flags().is_stable());
+    }
+
+    #[test]
+    fn significantly_differs_from_test() {
+        let base = Reading { co2: 1000, temperature: Some(20), status: 0x40, below_floor: false, saturated: false };
+
+        let reading = |co2, temperature| Reading { co2, temperature, ..base };
+
+        assert!(!base.significantly_differs_from(&base, 50, 2));
+        assert!(!reading(1049, Some(20)).significantly_differs_from(&base, 50, 2));
+        assert!(reading(1050, Some(20)).significantly_differs_from(&base, 50, 2));
+        assert!(!reading(951, Some(20)).significantly_differs_from(&base, 50, 2));
+        assert!(reading(950, Some(20)).significantly_differs_from(&base, 50, 2));
+        assert!(!reading(1000, Some(21)).significantly_differs_from(&base, 50, 2));
+        assert!(reading(1000, Some(22)).significantly_differs_from(&base, 50, 2));
+        assert!(reading(1000, Some(18)).significantly_differs_from(&base, 50, 2));
+        assert!(reading(1000, None).significantly_differs_from(&base, 50, 2));
+        assert!(!reading(1000, None).significantly_differs_from(&reading(1000, None), 50, 2));
+    }
+
+    #[test]
+    fn to_csv_line_test() {
+        let reading = Reading { co2: 1200, temperature: Some(24), status: 0x40, below_floor: false, saturated: false };
+
+        assert_eq!(reading.to_csv_line(1_700_000_000).as_str(), "1700000000,1200,24,64");
+        assert_eq!(Reading { temperature: None, ..reading }.to_csv_line(0).as_str(), "0,1200,,64");
+
+        let widest = Reading { co2: u16::MAX, temperature: Some(i16::MIN), status: u8::MAX, ..reading };
+
+        assert_eq!(widest.to_csv_line(u32::MAX).as_str(), "4294967295,65535,-32768,255");
+    }
+
+    #[test]
+    fn status_description_test() {
+        assert_eq!(status_description(0x00), "warming up");
+        assert_eq!(status_description(0x40), "normal");
+        assert_eq!(status_description(0x01), "error");
+        assert_eq!(status_description(0x41), "unknown");
+        assert_eq!(status_description(0xFF), "unknown");
+    }
+
+    #[test]
+    fn warmup_sentinels_test() {
+        assert!(Model::B.is_warmup_value(0));
+        assert!(Model::B.is_warmup_value(410));
+        assert!(!Model::B.is_warmup_value(500));
+        assert!(!Model::B.is_warmup_value(400));
+
+        assert!(Model::C.is_warmup_value(0));
+        assert!(Model::C.is_warmup_value(500));
+        assert!(!Model::C.is_warmup_value(410));
+        assert!(!Model::C.is_warmup_value(400));
+
+        let mut input = Vec::new();
+        let mut output = replies(&[reading_frame(410, 0x40)]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
         let mut mhz = Mhz19::new(serial);
 
-        return match mhz.response() {
-            Ok(_) => { Ok(()) }
-            _ => { Err("Can't read successful response") }
-        };
+        // MH-Z19B reports 410 ppm while warming up
+        assert!(mhz.is_warmup_value(410));
+        assert_eq!(mhz.is_warming_up(), Ok(true));
+    }
+
+    #[cfg(feature = "experimental")]
+    #[test]
+    fn raw_transact_traced_test() {
+        // frame with broken checksum is still traced
+        let broken: [u8; 9] = [0xFF, 0xA0, 0x30, 0x34, 0x33, 0x30, 0x00, 0x00, 0x00];
+
+        let mut input = Vec::new();
+        let mut output = replies(&[broken]);
+
+        let mut traced: [u8; 9] = [0; 9];
+        let mut count: usize = 0;
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        let result = mhz.raw_transact_traced(0xA0, [0; 5], |index, byte| {
+            traced[index] = byte;
+            count += 1;
+        });
+
+        assert_eq!(result, Err(Errors::Checksum));
+        assert_eq!(count, 9);
+        assert_eq!(traced, broken);
+    }
+
+    #[test]
+    fn co2_filtered_test() {
+        let mut input = Vec::new();
+        let mut output = replies(&[1200, 5000, 1210, 5000, 2500].map(|co2| reading_frame(co2, 0x40)));
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.co2_filtered(500), Ok(1200));
+        // glitch is read again
+        assert_eq!(mhz.co2_filtered(500), Ok(1210));
+        // re-read agrees with neither
+        assert_eq!(mhz.co2_filtered(500), Err(Errors::InvalidResponse));
+    }
+
+    #[test]
+    fn co2_filtered_step_test() {
+        let mut input = Vec::new();
+        let mut output = replies(&[1200, 400, 400, 400, 400, 400, 400].map(|co2| reading_frame(co2, 0x40)));
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.co2_filtered(500), Ok(1200));
+
+        // window opened: jump is confirmed by re-read and followed
+        for _ in 0..5 {
+            assert_eq!(mhz.co2_filtered(500), Ok(400));
+        }
+    }
+
+    #[test]
+    fn measure_with_quality_test() {
+        let good = reading_frame(1200, 0x40);
+
+        // zero concentration, implausible temperature, error flag and broken checksum
+        let bad: [u8; 9] = [0xFF, 0x86, 0x00, 0x00, 0xFF, 0x01, 0x00, 0x00, 0x00];
+
+        // only broken checksum
+        let mut corrupted = good;
+        corrupted[8] ^= 0x01;
+
+        let mut input = Vec::new();
+        let mut output = replies(&[good, bad, corrupted]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.measure_with_quality().map(|(reading, quality)| (reading.co2, quality)), Ok((1200, 100)));
+        assert_eq!(mhz.measure_with_quality().map(|(_, quality)| quality), Ok(0));
+        assert_eq!(mhz.measure_with_quality().map(|(_, quality)| quality), Ok(100 - QUALITY_CHECKSUM_WEIGHT));
+    }
+
+    #[test]
+    fn write_command_to_test() {
+        let mut line = String::new();
+
+        write_command_to(&mut line, 0x86, [0; 5]).unwrap();
+        assert_eq!(line, "FF 01 86 00 00 00 00 00 79");
+
+        let mut line = String::new();
+
+        write_command_to(&mut line, 0x99, Range::_2000.data()).unwrap();
+        assert_eq!(line, "FF 01 99 00 00 00 07 D0 8F");
+    }
+
+    #[test]
+    fn raw_transact_multi_test() {
+        let first = packet([0xFF, 0xA0, 0x30, 0x34, 0x33, 0x30, 0x00, 0x00]);
+        let second = packet([0xFF, 0xA0, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]);
+        let broken: [u8; 9] = [0xFF, 0xA0, 0, 0, 0, 0, 0, 0, 0];
+
+        let mut input = Vec::new();
+        let mut output = replies(&[first, second, broken]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.raw_transact_multi(0xA0, [0; 5], 5), Err(Errors::Unsupported));
+
+        let frames = mhz.raw_transact_multi(0xA0, [0; 5], 2).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], first);
+        assert_eq!(frames[1], second);
+
+        assert_eq!(mhz.raw_transact_multi(0xA0, [0; 5], 1), Err(Errors::Checksum));
+
+        assert_eq!(frames.capacity(), MAX_RESPONSE_FRAMES);
+        assert_eq!(MAX_RESPONSE_LEN, 36);
+    }
+
+    #[test]
+    fn saturated_test() {
+        let mut input = Vec::new();
+        let mut output = replies(&[2000, 1999, 2000].map(|co2| reading_frame(co2, 0x40)));
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        // factory range until configured
+        assert_eq!(mhz.full_scale(), 5000);
+        assert!(!mhz.measure().unwrap().saturated);
+
+        assert!(mhz.range(Range::_2000).is_ok());
+        assert_eq!(mhz.full_scale(), 2000);
+
+        assert!(!mhz.measure().unwrap().saturated);
+        assert!(mhz.measure().unwrap().saturated);
+    }
+
+    #[test]
+    fn const_new_test() {
+        struct NullSerial;
+
+        impl Read<u8> for NullSerial {
+            type Error = ();
+
+            fn read(&mut self) -> nb::Result<u8, Self::Error> { Err(nb::Error::Other(())) }
+        }
+
+        impl Write<u8> for NullSerial {
+            type Error = ();
+
+            fn write(&mut self, _word: u8) -> nb::Result<(), Self::Error> { Ok(()) }
+            fn flush(&mut self) -> nb::Result<(), Self::Error> { Ok(()) }
+        }
+
+        const MHZ: Mhz19<NullSerial> = Mhz19::new(NullSerial);
+
+        let mut mhz = MHZ;
+
+        assert_eq!(mhz.full_scale(), 5000);
+        assert_eq!(mhz.co2(), Err(Errors::ReadHardware));
+    }
+
+    #[test]
+    fn listen_test() {
+        let reading = reading_frame(1200, 0x40);
+        let command = packet([0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+        let mut corrupted = reading;
+        corrupted[3] ^= 0x10;
+
+        // noise, other master's command, corrupted frame, finally valid response
+        let mut input = Vec::new();
+        let mut output: Vec<u8, consts::U64> = Vec::new();
+
+        output.extend_from_slice(&[0x12, 0x34]).unwrap();
+        output.extend_from_slice(&command).unwrap();
+        output.extend_from_slice(&corrupted).unwrap();
+        output.extend_from_slice(&reading).unwrap();
+
+        {
+            let serial = DummySerial::new(&mut input, &mut output);
+            let mut mhz = Mhz19::new(serial);
+
+            assert_eq!(mhz.listen().map(|reading| reading.co2), Ok(1200));
+            assert_eq!(mhz.listen(), Err(Errors::ReadHardware));
+        }
+
+        assert!(input.is_empty());
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn temperature_f_test() {
+        // 100, -10 and 210 (implausible) °C
+        let mut input = Vec::new();
+        let mut output = replies(&[140, 30, 250].map(|byte| reading_frame(1200, byte)));
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.temperature_f(), Ok(212.0));
+        assert_eq!(mhz.temperature_f(), Ok(14.0));
+        assert_eq!(mhz.temperature_f(), Err(Errors::InvalidResponse));
+    }
+
+    #[test]
+    fn raw_adc_test() {
+        // raw value, then stale answer to other command
+        let raw = packet([0xFF, 0x84, 0x7A, 0x1C, 0x00, 0x00, 0x00, 0x00]);
+
+        let mut input = Vec::new();
+        let mut output = replies(&[raw, reading_frame(1200, 0x40)]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.raw_adc(), Ok(0x7A1C));
+        assert_eq!(mhz.raw_adc(), Err(Errors::UnexpectedCommand { expected: 0x84, got: 0x86 }));
+
+        mhz.set_model(Model::C);
+
+        assert_eq!(mhz.raw_adc(), Err(Errors::Unsupported));
+    }
+
+    #[test]
+    fn byte_swap_check_test() {
+        // 1200 ppm (0x04B0) with swapped bytes is 45060 ppm
+        let mut input = Vec::new();
+        let mut output = replies(&[reading_frame(0xB004, 0x40); 3]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::with_range(serial, Range::_2000).unwrap();
+
+        assert_eq!(mhz.co2(), Err(Errors::ImplausibleValue));
+
+        mhz.set_byte_swap_check(true);
+
+        assert_eq!(mhz.co2(), Err(Errors::PossibleByteSwap));
+
+        mhz.set_byte_swap_check(false);
+        mhz.set_max_plausible(u16::MAX);
+
+        assert_eq!(mhz.co2(), Ok(45060));
+    }
+
+    #[test]
+    fn max_plausible_test() {
+        let mut input = Vec::new();
+        let mut output = replies(&[10000, 10001, 60000].map(|co2| reading_frame(co2, 0x40)));
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.co2(), Ok(10000));
+        assert_eq!(mhz.co2(), Err(Errors::ImplausibleValue));
+
+        mhz.set_max_plausible(u16::MAX);
+
+        assert_eq!(mhz.co2(), Ok(60000));
+    }
+
+    #[test]
+    fn error_code_test() {
+        assert_eq!(Errors::Write.code(), 1);
+        assert_eq!(Errors::Read.code(), 2);
+        assert_eq!(Errors::Checksum.code(), 3);
+        assert_eq!(Errors::from_code(0), None);
+
+        for code in 0..=u8::MAX {
+            if let Some(error) = Errors::from_code(code) {
+                assert_eq!(error.code(), code);
+            }
+        }
+
+        assert_eq!((0..=u8::MAX).filter_map(Errors::from_code).count(), 17);
+
+        assert!(Errors::ReadWouldBlockTimeout.is_transient());
+        assert!(!Errors::ReadHardware.is_transient());
+        assert!(!Errors::Unsupported.is_transient());
+    }
+
+    #[test]
+    fn new_checked_test() {
+        // frame with broken checksum
+        let mut input = Vec::new();
+        let mut output = replies(&[[0x00; 9]]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+
+        assert!(matches!(Mhz19::new_checked(serial), Err(Errors::NoSensor)));
+    }
+
+    #[test]
+    fn take_last_error_test() {
+        // frame with broken checksum
+        let mut input = Vec::new();
+        let mut output = replies(&[[0xFF, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]]);
+
+        let serial = DummySerial::new(&mut input, &mut output);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.take_last_error(), None);
+        assert!(mhz.response().is_err());
+        assert_eq!(mhz.take_last_error(), Some(Errors::Checksum));
+        assert_eq!(mhz.take_last_error(), None);
     }
 }
@@ -0,0 +1,62 @@
+use embedded_hal::timer::{CountDown, Periodic};
+use crate::mhz19::{Errors, Mhz19Trait, Reading};
+
+///
+/// Non-blocking "read every N seconds" primitive for super-loop firmware.
+/// Uses periodic timer, so count down restarts in hardware right after it
+/// finishes: period doesn't drift with measurement time and there is no
+/// counter wrap to handle. If a measurement takes longer than the period,
+/// missed periods are skipped rather than queued.
+///
+/// # Example
+///
+/// ```
+/// let mut timer = Timer::tim2(...);
+/// let mhz = Mhz19::new(serial);
+///
+/// let mut sampler = PeriodicSampler::new(mhz, timer, 5.secs());
+///
+/// loop {
+///     if let Some(result) = sampler.poll() {
+///         // handle reading
+///     }
+///     // other work
+/// }
+/// ```
+pub struct PeriodicSampler<Mhz19Type, TimerType>
+    where
+        Mhz19Type: Mhz19Trait<Error = Errors>,
+        TimerType: CountDown + Periodic
+{
+    mhz: Mhz19Type,
+    timer: TimerType
+}
+
+impl<Mhz19Type, TimerType> PeriodicSampler<Mhz19Type, TimerType>
+    where
+        Mhz19Type: Mhz19Trait<Error = Errors>,
+        TimerType: CountDown + Periodic
+{
+    /// Create sampler and start timer with `interval`
+    pub fn new<TimeType>(mhz: Mhz19Type, mut timer: TimerType, interval: TimeType) -> Self
+        where
+            TimeType: Into<TimerType::Time>
+    {
+        timer.start(interval);
+
+        Self { mhz, timer }
+    }
+
+    /// Returns reading when interval elapsed, `None` otherwise
+    pub fn poll(&mut self) -> Option<Result<Reading, Errors>> {
+        match self.timer.wait() {
+            Ok(_) => { Some(self.mhz.measure()) }
+            Err(_) => { None }
+        }
+    }
+
+    /// Access driver between samples, e.g. for configuration
+    pub fn mhz(&mut self) -> &mut Mhz19Type {
+        &mut self.mhz
+    }
+}
@@ -0,0 +1,52 @@
+use crate::mhz19::{Mhz19Trait, Reading};
+
+///
+/// Generic sensor interface for frameworks which aggregate different sensors.
+/// There is no common external trait in embedded-hal 0.2 ecosystem this crate
+/// could target, so the trait is defined here in the minimal shape such frameworks
+/// use (one fallible read of a measurement). Implemented for every [`Mhz19Trait`]
+/// driver. Framework trait can't be implemented for `Mhz19` outside of this crate
+/// (orphan rule: both are foreign there), so application wraps driver in own
+/// newtype and forwards to [`Sensor::read`].
+///
+/// Available with the `sensor` feature.
+///
+/// # Example
+///
+/// ```
+/// fn log_all<S: Sensor>(sensors: &mut [S]) {
+///     for sensor in sensors.iter_mut() {
+///         if let Ok(measurement) = sensor.read() {
+///             // ...
+///         }
+///     }
+/// }
+///
+/// // in application crate
+/// struct Co2Sensor<M>(M);
+///
+/// impl<M: Sensor<Measurement = Reading>> framework::Sensor for Co2Sensor<M> {
+///     fn sample(&mut self) -> Option<f32> {
+///         self.0.read().ok().map(|reading| reading.co2 as f32)
+///     }
+/// }
+/// ```
+pub trait Sensor {
+    type Measurement;
+    type Error;
+
+    /// Take one measurement
+    fn read(&mut self) -> Result<Self::Measurement, Self::Error>;
+}
+
+impl<Mhz19Type> Sensor for Mhz19Type
+    where
+        Mhz19Type: Mhz19Trait
+{
+    type Measurement = Reading;
+    type Error = Mhz19Type::Error;
+
+    fn read(&mut self) -> Result<Self::Measurement, Self::Error> {
+        self.measure()
+    }
+}
@@ -0,0 +1,132 @@
+use std::io;
+use embedded_hal::serial::{Read, Write};
+#[cfg(feature = "read-buf")]
+use crate::mhz19::ReadBuf;
+
+///
+/// Adapter of `std::io` serial port (e.g. `/dev/ttyUSB0` opened as file or with
+/// any serial crate) to embedded-hal serial traits, so [`Mhz19`](crate::mhz19::Mhz19)
+/// can be used on Linux gateways. Port timeouts (`WouldBlock`, `TimedOut`) are
+/// reported as `nb::Error::WouldBlock`.
+///
+/// Available with the `std-serial` feature.
+///
+/// # Example
+///
+/// ```
+/// let port = std::fs::OpenOptions::new().read(true).write(true).open("/dev/ttyUSB0")?;
+/// let mut mhz = Mhz19::new(StdSerial::new(port));
+///
+/// let co2: u16 = mhz.co2().unwrap();
+/// ```
+pub struct StdSerial<PortType>
+    where
+        PortType: io::Read + io::Write
+{
+    port: PortType
+}
+
+impl<PortType> StdSerial<PortType>
+    where
+        PortType: io::Read + io::Write
+{
+    pub fn new(port: PortType) -> Self {
+        Self { port }
+    }
+
+    /// Get wrapped port back
+    pub fn into_inner(self) -> PortType {
+        self.port
+    }
+}
+
+// Timeouts are not errors for nb
+fn nb_error(error: io::Error) -> nb::Error<io::Error> {
+    match error.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted => {
+            nb::Error::WouldBlock
+        }
+        _ => { nb::Error::Other(error) }
+    }
+}
+
+impl<PortType> Read<u8> for StdSerial<PortType>
+    where
+        PortType: io::Read + io::Write
+{
+    type Error = io::Error;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        let mut byte: [u8; 1] = [0];
+
+        match self.port.read(&mut byte) {
+            Ok(1) => { Ok(byte[0]) }
+            Ok(_) => { Err(nb::Error::WouldBlock) }
+            Err(error) => { Err(nb_error(error)) }
+        }
+    }
+}
+
+impl<PortType> Write<u8> for StdSerial<PortType>
+    where
+        PortType: io::Read + io::Write
+{
+    type Error = io::Error;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.port.write_all(&[word]).map_err(nb_error)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.port.flush().map_err(nb_error)
+    }
+}
+
+#[cfg(feature = "read-buf")]
+impl<PortType> ReadBuf for StdSerial<PortType>
+    where
+        PortType: io::Read + io::Write
+{
+    type Error = io::Error;
+
+    fn read_buf(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error> {
+        self.port.read_exact(buffer).map_err(nb_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mhz19::{Mhz19, Mhz19Trait};
+
+    struct DummyPort {
+        input: Vec<u8>,
+        output: io::Cursor<Vec<u8>>
+    }
+
+    impl io::Read for DummyPort {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.output.read(buf)
+        }
+    }
+
+    impl io::Write for DummyPort {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.input.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    #[test]
+    fn std_serial_test() {
+        let port = DummyPort {
+            input: Vec::new(),
+            output: io::Cursor::new(vec![0xFF, 0x86, 0x04, 0xB0, 0x40, 0x00, 0x00, 0x00, 0x86])
+        };
+
+        let mut mhz = Mhz19::new(StdSerial::new(port));
+
+        assert_eq!(mhz.co2(), Ok(1200));
+    }
+}
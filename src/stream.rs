@@ -0,0 +1,94 @@
+use core::future::{poll_fn, Future};
+use core::task::Poll;
+use core::time::Duration;
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::serial::Write;
+use crate::mhz19::{Errors, Mhz19, ReadFrame, Reading};
+
+///
+/// Async timer for [`ReadingStream`], e.g. wrapper of `embassy_time::Timer::after()`.
+/// Returned future must count `duration` from the call of `after()`, not from the
+/// first poll, otherwise period of the stream drifts with measurement time.
+///
+/// Available with the `async` feature.
+pub trait AsyncTimer {
+    type Wait: Future<Output = ()>;
+
+    /// Future which completes `duration` after this call
+    fn after(&mut self, duration: Duration) -> Self::Wait;
+}
+
+///
+/// Periodic async stream of readings, created by `Mhz19::stream()`. First reading is
+/// taken immediately, next ones every `interval` counted from start of previous
+/// transaction. If a transaction takes longer than the interval, next one starts
+/// right after it: transactions never overlap and missed periods are not queued.
+///
+/// Serial is polled without blocking (`Mhz19::read_measurement()`); on `WouldBlock`
+/// the task wakes itself, so a pending read keeps the executor busy until the frame
+/// arrives. Stream is endless, `next()` always returns `Some`.
+///
+/// Available with the `async` feature.
+///
+/// # Example
+///
+/// ```
+/// let mut mhz = Mhz19::new(serial);
+/// let mut stream = mhz.stream(EmbassyTimer, Duration::from_secs(5));
+///
+/// while let Some(result) = stream.next().await {
+///     // handle reading
+/// }
+/// ```
+pub struct ReadingStream<'a, SerialType, DelayType, TimerType>
+    where
+        SerialType: Write<u8> + ReadFrame,
+        DelayType: DelayMs<u16>,
+        TimerType: AsyncTimer
+{
+    mhz: &'a mut Mhz19<SerialType, DelayType>,
+    timer: TimerType,
+    interval: Duration,
+    wait: Option<TimerType::Wait>
+}
+
+impl<'a, SerialType, DelayType, TimerType> ReadingStream<'a, SerialType, DelayType, TimerType>
+    where
+        SerialType: Write<u8> + ReadFrame,
+        DelayType: DelayMs<u16>,
+        TimerType: AsyncTimer
+{
+    pub fn new(mhz: &'a mut Mhz19<SerialType, DelayType>, timer: TimerType, interval: Duration) -> Self {
+        Self {
+            mhz,
+            timer,
+            interval,
+            wait: None
+        }
+    }
+
+    /// Wait for next period and read sensor
+    pub async fn next(&mut self) -> Option<Result<Reading, Errors>> {
+        if let Some(wait) = self.wait.take() {
+            wait.await;
+        }
+
+        self.wait = Some(self.timer.after(self.interval));
+
+        let mhz = &mut *self.mhz;
+
+        let result = poll_fn(|cx| {
+            match mhz.read_measurement() {
+                Ok(reading) => { Poll::Ready(Ok(reading)) }
+                Err(nb::Error::WouldBlock) => {
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+                Err(nb::Error::Other(error)) => { Poll::Ready(Err(error)) }
+            }
+        })
+        .await;
+
+        Some(result)
+    }
+}
@@ -0,0 +1,386 @@
+use embedded_hal::serial::{Read, Write};
+#[cfg(feature = "read-buf")]
+use crate::mhz19::ReadBuf;
+use crate::mhz19::{Checksum, StandardChecksum, FRAME_LEN, START_BYTE};
+
+/// Build frame from first 8 bytes, appending MH-Z19 checksum
+pub fn frame(data: [u8; FRAME_LEN - 1]) -> [u8; FRAME_LEN] {
+    let mut frame: [u8; FRAME_LEN] = [0; FRAME_LEN];
+
+    frame[..FRAME_LEN - 1].copy_from_slice(&data);
+    frame[FRAME_LEN - 1] = StandardChecksum.checksum(&data);
+
+    frame
+}
+
+/// Frames are equal in all bytes but checksum (last one)
+pub fn frames_equal_ignoring_crc(a: &[u8; FRAME_LEN], b: &[u8; FRAME_LEN]) -> bool {
+    a[..FRAME_LEN - 1] == b[..FRAME_LEN - 1]
+}
+
+/// Statistics of captured byte stream, see [`validate_capture`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CaptureReport {
+    /// Frames with valid checksum
+    pub frames: usize,
+    /// Frames starting with start byte but with wrong checksum
+    pub checksum_failures: usize,
+    /// Runs of bytes skipped while searching for start byte
+    pub desyncs: usize,
+    /// Bytes skipped while searching for start byte
+    pub skipped_bytes: usize,
+    /// Bytes of incomplete frame at the end of capture
+    pub trailing_bytes: usize
+}
+
+///
+/// Walk captured byte stream (e.g. logic analyzer export) with the same rules as
+/// [`FrameParser`](crate::mhz19::FrameParser): bytes before start byte are skipped,
+/// repeated start bytes are collapsed, after wrong checksum search restarts from the
+/// byte after start byte. Frames use MH-Z19 start byte and checksum.
+///
+/// # Example
+///
+/// ```
+/// let capture = std::fs::read("tests/captures/boot.bin").unwrap();
+/// let report = validate_capture(&capture);
+///
+/// assert_eq!(report.checksum_failures, 0);
+/// ```
+pub fn validate_capture(bytes: &[u8]) -> CaptureReport {
+    let mut report = CaptureReport::default();
+    let mut skipping = false;
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index] != START_BYTE {
+            if !skipping {
+                report.desyncs += 1;
+                skipping = true;
+            }
+
+            report.skipped_bytes += 1;
+            index += 1;
+            continue;
+        }
+
+        skipping = false;
+
+        // stuffed start byte, frame starts from the last one
+        if bytes.get(index + 1) == Some(&START_BYTE) {
+            index += 1;
+            continue;
+        }
+
+        let frame = match bytes.get(index..index + FRAME_LEN) {
+            Some(frame) => { frame }
+            None => {
+                report.trailing_bytes = bytes.len() - index;
+                break;
+            }
+        };
+
+        if StandardChecksum.checksum(&frame[..FRAME_LEN - 1]) == frame[FRAME_LEN - 1] {
+            report.frames += 1;
+            index += FRAME_LEN;
+        } else {
+            report.checksum_failures += 1;
+            index += 1;
+        }
+    }
+
+    report
+}
+
+/// One exchange of [`ScriptedSerial`]: frame driver must send and sensor answer to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub tx: [u8; FRAME_LEN],
+    pub rx: Option<[u8; FRAME_LEN]>
+}
+
+impl Step {
+    /// Command answered with `rx`
+    pub const fn reply(tx: [u8; FRAME_LEN], rx: [u8; FRAME_LEN]) -> Self {
+        Self { tx, rx: Some(rx) }
+    }
+
+    /// Command without response (calibration, configuration)
+    pub const fn silent(tx: [u8; FRAME_LEN]) -> Self {
+        Self { tx, rx: None }
+    }
+}
+
+///
+/// Serial mock playing back a script of exchanges for protocol conformance tests.
+/// Every frame written by driver is compared with the expected one of current step
+/// (panics on mismatch or on frame past the end of script), then canned response
+/// of the step becomes readable. Reading with no response pending fails instead
+/// of blocking.
+///
+/// Available with the `test-util` feature.
+///
+/// # Example
+///
+/// ```
+/// let script = [
+///     Step::reply(
+///         frame([0xFF, 0x01, 0x86, 0, 0, 0, 0, 0]),
+///         frame([0xFF, 0x86, 0x04, 0xB0, 0x40, 0, 0, 0])
+///     )
+/// ];
+///
+/// let mut mhz = Mhz19::new(ScriptedSerial::new(&script));
+///
+/// assert_eq!(mhz.co2(), Ok(1200));
+/// ```
+pub struct ScriptedSerial<'a> {
+    script: &'a [Step],
+    step: usize,
+    tx: [u8; FRAME_LEN],
+    tx_len: usize,
+    rx: [u8; FRAME_LEN],
+    rx_index: usize
+}
+
+impl<'a> ScriptedSerial<'a> {
+    pub fn new(script: &'a [Step]) -> Self {
+        Self {
+            script,
+            step: 0,
+            tx: [0; FRAME_LEN],
+            tx_len: 0,
+            rx: [0; FRAME_LEN],
+            rx_index: FRAME_LEN
+        }
+    }
+
+    /// All steps of script are done
+    pub fn is_finished(&self) -> bool {
+        self.step == self.script.len() && self.tx_len == 0
+    }
+
+    /// Panics if some steps of script are not done
+    pub fn assert_finished(&self) {
+        assert!(
+            self.is_finished(),
+            "script is not finished: {} of {} steps done",
+            self.step,
+            self.script.len()
+        );
+    }
+
+    // Check complete frame written by driver against the script
+    fn process(&mut self) {
+        let step = match self.script.get(self.step) {
+            Some(step) => { step }
+            None => { panic!("unexpected frame after end of script: {:02X?}", self.tx); }
+        };
+
+        assert_eq!(self.tx, step.tx, "unexpected frame at step {}", self.step);
+
+        if let Some(rx) = step.rx {
+            self.rx = rx;
+            self.rx_index = 0;
+        }
+
+        self.step += 1;
+    }
+}
+
+impl<'a> Write<u8> for ScriptedSerial<'a> {
+    type Error = ();
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.tx[self.tx_len] = word;
+        self.tx_len += 1;
+
+        if self.tx_len == FRAME_LEN {
+            self.tx_len = 0;
+            self.process();
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> { Ok(()) }
+}
+
+impl<'a> Read<u8> for ScriptedSerial<'a> {
+    type Error = ();
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        match self.rx.get(self.rx_index) {
+            Some(&byte) => {
+                self.rx_index += 1;
+                Ok(byte)
+            }
+            None => { Err(nb::Error::Other(())) }
+        }
+    }
+}
+
+#[cfg(feature = "read-buf")]
+impl<'a> ReadBuf for ScriptedSerial<'a> {
+    type Error = ();
+
+    fn read_buf(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error> {
+        if FRAME_LEN - self.rx_index < buffer.len() {
+            return Err(nb::Error::Other(()));
+        }
+
+        for data in buffer.iter_mut() {
+            *data = self.read()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fault rates of [`FaultyMock`], each in percent of received bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Faults {
+    /// Byte is lost
+    pub drop_percent: u8,
+    /// One bit of byte is flipped, so frame fails checksum (or start byte check)
+    pub corrupt_percent: u8,
+    /// Byte is delayed by `delay_polls` `WouldBlock` polls
+    pub delay_percent: u8,
+    pub delay_polls: u32,
+    /// Read fails with hardware error (`nb::Error::Other`)
+    pub hardware_error_percent: u8
+}
+
+///
+/// Serial wrapper injecting faults into bytes received from wrapped serial (e.g.
+/// [`ScriptedSerial`]) at configured rates, for testing retry and recovery logic.
+/// Faults are drawn from a pseudo random generator with given seed, so every run
+/// with the same seed fails the same way. Written bytes are passed through. Delays
+/// and drops with a serial which blocks forever when empty can hang the driver,
+/// limit polls with `Mhz19::set_read_timeout_polls()`.
+///
+/// Available with the `test-util` feature.
+///
+/// # Example
+///
+/// ```
+/// let faults = Faults { corrupt_percent: 10, ..Faults::default() };
+/// let serial = FaultyMock::new(ScriptedSerial::new(&script), faults, 42);
+/// let mut mhz = Mhz19::new(serial);
+///
+/// // application retry logic must cope with ~60 % broken frames
+/// ```
+pub struct FaultyMock<SerialType> {
+    serial: SerialType,
+    faults: Faults,
+    state: u32,
+    delay_left: u32,
+    delayed: bool
+}
+
+impl<SerialType> FaultyMock<SerialType> {
+    pub fn new(serial: SerialType, faults: Faults, seed: u32) -> Self {
+        Self {
+            serial,
+            faults,
+            // xorshift state must not be zero
+            state: if seed == 0 { 0x9E37_79B9 } else { seed },
+            delay_left: 0,
+            delayed: false
+        }
+    }
+
+    /// Change fault rates, e.g. to inject faults only after initialization
+    pub fn set_faults(&mut self, faults: Faults) {
+        self.faults = faults;
+    }
+
+    /// Take back wrapped serial
+    pub fn free(self) -> SerialType {
+        self.serial
+    }
+
+    // xorshift32
+    fn next_random(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state
+    }
+
+    fn chance(&mut self, percent: u8) -> bool {
+        percent > 0 && self.next_random() % 100 < percent as u32
+    }
+}
+
+impl<SerialType> Write<u8> for FaultyMock<SerialType>
+    where
+        SerialType: Write<u8>
+{
+    type Error = ();
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.serial.write(word).map_err(|error| error.map(|_| ()))
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.serial.flush().map_err(|error| error.map(|_| ()))
+    }
+}
+
+impl<SerialType> Read<u8> for FaultyMock<SerialType>
+    where
+        SerialType: Read<u8>
+{
+    type Error = ();
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        if self.delay_left > 0 {
+            self.delay_left -= 1;
+            return Err(nb::Error::WouldBlock);
+        }
+
+        if !self.delayed && self.faults.delay_polls > 0 && self.chance(self.faults.delay_percent) {
+            self.delayed = true;
+            self.delay_left = self.faults.delay_polls - 1;
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.delayed = false;
+
+        loop {
+            if self.chance(self.faults.hardware_error_percent) {
+                return Err(nb::Error::Other(()));
+            }
+
+            let byte = self.serial.read().map_err(|error| error.map(|_| ()))?;
+
+            if self.chance(self.faults.drop_percent) {
+                continue;
+            }
+
+            if self.chance(self.faults.corrupt_percent) {
+                return Ok(byte ^ (1 << (self.next_random() % 8)));
+            }
+
+            return Ok(byte);
+        }
+    }
+}
+
+#[cfg(feature = "read-buf")]
+impl<SerialType> ReadBuf for FaultyMock<SerialType>
+    where
+        SerialType: Read<u8>
+{
+    type Error = ();
+
+    // Byte by byte, waiting out delays, so partial frame is never lost
+    fn read_buf(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error> {
+        for data in buffer.iter_mut() {
+            *data = nb::block!(self.read()).map_err(nb::Error::Other)?;
+        }
+
+        Ok(())
+    }
+}
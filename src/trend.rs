@@ -0,0 +1,117 @@
+///
+/// Rate of change of concentration over last `N` readings, least squares fit over
+/// timestamped samples, e.g. to warn before ventilation is needed. Timestamps are
+/// milliseconds of any monotonic clock, counter wrap between oldest and newest
+/// sample is handled.
+///
+/// Available with the `float` feature.
+///
+/// # Example
+///
+/// ```
+/// let mut trend = TrendTracker::<10>::new();
+///
+/// trend.push(uptime_ms(), mhz.co2().unwrap());
+///
+/// if let Some(minutes) = trend.time_to_reach(1000) {
+///     // "open window in ~{} min"
+/// }
+/// ```
+pub struct TrendTracker<const N: usize> {
+    samples: [(u32, u16); N],
+    len: usize
+}
+
+impl<const N: usize> TrendTracker<N> {
+    pub const fn new() -> Self {
+        Self {
+            samples: [(0, 0); N],
+            len: 0
+        }
+    }
+
+    /// Record concentration `co2` measured at `timestamp_ms`, dropping the oldest sample when full
+    pub fn push(&mut self, timestamp_ms: u32, co2: u16) {
+        if N == 0 {
+            return;
+        }
+
+        if self.len == N {
+            self.samples.rotate_left(1);
+            self.len -= 1;
+        }
+
+        self.samples[self.len] = (timestamp_ms, co2);
+        self.len += 1;
+    }
+
+    /// Drop recorded samples
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Latest recorded concentration
+    pub fn latest(&self) -> Option<u16> {
+        self.samples[..self.len].last().map(|&(_, co2)| co2)
+    }
+
+    ///
+    /// Rate of change, ppm per minute. `None` with less than two samples or when
+    /// all samples have the same timestamp.
+    pub fn rate_ppm_per_min(&self) -> Option<f32> {
+        let samples = &self.samples[..self.len];
+        let (first_ms, _) = *samples.first()?;
+
+        if samples.len() < 2 {
+            return None;
+        }
+
+        let count = samples.len() as f32;
+        let minutes = |timestamp_ms: u32| timestamp_ms.wrapping_sub(first_ms) as f32 / 60_000.0;
+
+        let mean_t = samples.iter().map(|&(t, _)| minutes(t)).sum::<f32>() / count;
+        let mean_co2 = samples.iter().map(|&(_, co2)| co2 as f32).sum::<f32>() / count;
+
+        let mut covariance: f32 = 0.0;
+        let mut variance: f32 = 0.0;
+
+        for &(t, co2) in samples.iter() {
+            let dt = minutes(t) - mean_t;
+
+            covariance += dt * (co2 as f32 - mean_co2);
+            variance += dt * dt;
+        }
+
+        if variance == 0.0 {
+            return None;
+        }
+
+        Some(covariance / variance)
+    }
+
+    ///
+    /// Estimated minutes until concentration reaches `target_ppm` at current rate,
+    /// from latest sample. Zero if latest sample is already at target, `None` if trend
+    /// is flat or goes away from target (or rate is unknown, see `rate_ppm_per_min()`).
+    pub fn time_to_reach(&self, target_ppm: u16) -> Option<f32> {
+        let distance = target_ppm as f32 - self.latest()? as f32;
+
+        if distance == 0.0 {
+            return Some(0.0);
+        }
+
+        let rate = self.rate_ppm_per_min()?;
+
+        if rate == 0.0 || rate.is_sign_positive() != distance.is_sign_positive() {
+            return None;
+        }
+
+        Some(distance / rate)
+    }
+}
+
+impl<const N: usize> Default for TrendTracker<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,64 +1,208 @@
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::timer::{CountDown, Periodic};
 use embedded_hal::serial::{Read, Write};
+#[cfg(feature = "read-buf")]
+use mhz19_rs::mhz19::ReadBuf;
+#[cfg(feature = "async")]
+use mhz19_rs::stream::AsyncTimer;
 use std::collections::VecDeque;
+#[cfg(feature = "async")]
+use std::future::{ready, Future, Ready};
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+#[cfg(feature = "async")]
+use std::time::Duration;
 
 fn checksum(data: &[u8]) -> u8 {
     let mut result: u8 = 0;
 
     for &number in data {
-        match result.overflowing_add(number) {
-            (value, _) => { result = value; }
-        }
-    }
-
-    match 255_u8.overflowing_sub(result) {
-        (value, _) => { result = value; }
+        result = result.wrapping_add(number);
     }
 
-    result
+    255_u8.wrapping_sub(result)
 }
 
+/// Mock of sensor which keeps range and ABC state set by commands
 pub struct DummySerial {
     input: VecDeque<u8>,
-    output: VecDeque<u8>
+    output: VecDeque<u8>,
+    warmup: usize,
+    range: u16,
+    abc: bool,
+    stall: u32,
+    stalled: u32,
+    reply_len: usize,
+    broken: bool
+}
+
+impl Default for DummySerial {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DummySerial {
     pub fn new() -> Self {
         Self {
             input: VecDeque::new(),
-            output: VecDeque::new()
+            output: VecDeque::new(),
+            warmup: 0,
+            range: 5000,
+            abc: true,
+            stall: 0,
+            stalled: 0,
+            reply_len: 9,
+            broken: false
         }
     }
 
+    /// Sensor which answers with zero concentration for first `frames` reads
+    pub fn warming_up(frames: usize) -> Self {
+        Self {
+            warmup: frames,
+            ..Self::new()
+        }
+    }
+
+    /// Sensor whose UART reports `WouldBlock` `stall` times before each read
+    pub fn stalling(stall: u32) -> Self {
+        Self {
+            stall,
+            ..Self::new()
+        }
+    }
+
+    /// Sensor whose UART reports hardware error on every read
+    pub fn broken() -> Self {
+        Self {
+            broken: true,
+            ..Self::new()
+        }
+    }
+
+    // Report `WouldBlock` for configured number of polls
+    fn poll_stall(&mut self) -> bool {
+        if self.stalled < self.stall {
+            self.stalled += 1;
+            return true;
+        }
+
+        self.stalled = 0;
+        false
+    }
+
+    /// Sensor which answers with frames cut to `reply_len` bytes, last one is checksum
+    pub fn short_replies(reply_len: usize) -> Self {
+        Self {
+            reply_len,
+            ..Self::new()
+        }
+    }
+
+    /// Sensor with `noise` pending in its output, e.g. tail of interrupted frame
+    pub fn with_noise(noise: &[u8]) -> Self {
+        let mut serial = Self::new();
+
+        serial.output.extend(noise.iter());
+        serial
+    }
+
     fn process(&mut self, data: [u8; 9]) {
         if data[0] != 0xFF { return; }
         if data[1] != 0x01 { return; }
 
         let packet = match data[2] {
+            0x86 if self.warmup > 0 => {
+                self.warmup -= 1;
+
+                let mut packet: [u8; 9] = [0xFF, 0x86, 0, 0, 0, 0, 0, 0, 0];
+
+                packet[8] = checksum(&packet[0..8]);
+                packet
+            }
             0x86 => {
                 let mut packet: [u8; 9] = [
                     0xFF,
-                    0x01,
+                    0x86,
                     0x04,
                     0xB0,
+                    0x40,
                     0x00,
                     0x00,
                     0x00,
                     0x00,
+                ];
+
+                packet[8] = checksum(&packet[0..8]);
+                packet
+            }
+            0xA0 => {
+                let mut packet: [u8; 9] = [0xFF, 0xA0, b'0', b'4', b'4', b'3', 0x00, 0x00, 0x00];
+
+                packet[8] = checksum(&packet[0..8]);
+                packet
+            }
+            0x7D => {
+                let mut packet: [u8; 9] = [
+                    0xFF,
+                    0x7D,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    self.abc as u8,
                     0x00,
                 ];
 
                 packet[8] = checksum(&packet[0..8]);
                 packet
             }
+            0x9B => {
+                let mut packet: [u8; 9] = [
+                    0xFF,
+                    0x9B,
+                    0x00,
+                    0x00,
+                    (self.range >> 8) as u8,
+                    self.range as u8,
+                    0x00,
+                    0x00,
+                    0x00,
+                ];
+
+                packet[8] = checksum(&packet[0..8]);
+                packet
+            }
+            0x79 => {
+                // For this command no return value
+                self.abc = data[3] == 0xA0;
+                return;
+            }
+            0x99 => {
+                // For this command no return value
+                self.range = ((data[6] as u16) << 8) | (data[7] as u16);
+                return;
+            }
+            0x78 => {
+                // For this command no return value
+                self.range = 5000;
+                self.abc = true;
+                return;
+            }
             0x87 => { return; } // For this command no return value
             0x88 => { return; } // For this command no return value
             _ => { panic!("unexpected command!"); }
         };
 
-        for &b in packet.iter() {
+        let crc_index = self.reply_len - 1;
+
+        for &b in packet[..crc_index].iter() {
             self.output.push_back(b);
         }
+
+        self.output.push_back(checksum(&packet[..crc_index]));
     }
 
     fn receive(&mut self, data: u8) {
@@ -68,9 +212,8 @@ impl DummySerial {
             let mut parse_buffer: [u8; 9] = [0; 9];
 
             for d in parse_buffer.iter_mut() {
-                match self.input.pop_front() {
-                    Some(inp) => { *d = inp; }
-                    _ => {}
+                if let Some(inp) = self.input.pop_front() {
+                    *d = inp;
                 }
             }
 
@@ -97,7 +240,9 @@ impl Read<u8> for DummySerial {
     type Error = nb::Error<()>;
 
     fn read(&mut self) -> nb::Result<u8, Self::Error> {
-        if self.output.is_empty() {
+        if self.broken { return Err(nb::Error::Other(nb::Error::Other(()))); }
+
+        if self.output.is_empty() || self.poll_stall() {
             return Err(nb::Error::WouldBlock);
         }
 
@@ -108,3 +253,108 @@ impl Read<u8> for DummySerial {
         Err(nb::Error::WouldBlock)
     }
 }
+
+#[cfg(feature = "read-buf")]
+impl ReadBuf for DummySerial {
+    type Error = nb::Error<()>;
+
+    fn read_buf(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error> {
+        if self.broken { return Err(nb::Error::Other(nb::Error::Other(()))); }
+
+        if self.output.len() < buffer.len() || self.poll_stall() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        for data in buffer.iter_mut() {
+            if let Some(byte) = self.output.pop_front() {
+                *data = byte;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Delay which only counts requested time
+#[derive(Default)]
+pub struct DummyDelay {
+    pub elapsed_ms: u32
+}
+
+impl DelayMs<u16> for DummyDelay {
+    fn delay_ms(&mut self, ms: u16) {
+        self.elapsed_ms += ms as u32;
+    }
+}
+
+impl DelayMs<u16> for &mut DummyDelay {
+    fn delay_ms(&mut self, ms: u16) {
+        self.elapsed_ms += ms as u32;
+    }
+}
+
+/// Periodic timer which fires every `period` calls of `wait`
+#[derive(Default)]
+pub struct DummyTimer {
+    period: u32,
+    count: u32
+}
+
+impl CountDown for DummyTimer {
+    type Time = u32;
+
+    fn start<T>(&mut self, count: T)
+        where
+            T: Into<Self::Time>
+    {
+        self.period = count.into();
+        self.count = 0;
+    }
+
+    fn wait(&mut self) -> nb::Result<(), void::Void> {
+        self.count += 1;
+
+        if self.count < self.period {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.count = 0;
+
+        Ok(())
+    }
+}
+
+impl Periodic for DummyTimer {}
+
+/// Async timer which completes immediately and only records requested durations
+#[cfg(feature = "async")]
+#[derive(Default)]
+pub struct DummyAsyncTimer {
+    pub requested: Vec<Duration>
+}
+
+#[cfg(feature = "async")]
+impl AsyncTimer for &mut DummyAsyncTimer {
+    type Wait = Ready<()>;
+
+    fn after(&mut self, duration: Duration) -> Self::Wait {
+        self.requested.push(duration);
+        ready(())
+    }
+}
+
+/// Run future to completion by busy polling
+#[cfg(feature = "async")]
+pub fn block_on<FutureType>(future: FutureType) -> FutureType::Output
+    where
+        FutureType: Future
+{
+    let mut future = std::pin::pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
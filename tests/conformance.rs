@@ -0,0 +1,290 @@
+#![cfg(feature = "test-util")]
+
+#[cfg(test)]
+mod tests {
+    use mhz19_rs::mhz19::{AutoCalibrationState, Errors, Mhz19, Mhz19Trait, Range, SensorConfig};
+    use mhz19_rs::mhz19::{decode_first_frame, FrameParser, START_BYTE};
+    use mhz19_rs::test_util::{
+        frame,
+        frames_equal_ignoring_crc,
+        validate_capture,
+        CaptureReport,
+        FaultyMock,
+        Faults,
+        ScriptedSerial,
+        Step
+    };
+
+    #[test]
+    fn all_commands_test() {
+        let script = [
+            Step::reply(
+                frame([0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                frame([0xFF, 0x86, 0x04, 0xB0, 0x40, 0x00, 0x00, 0x00])
+            ),
+            Step::silent(frame([0xFF, 0x01, 0x87, 0x00, 0x00, 0x00, 0x00, 0x00])),
+            Step::silent(frame([0xFF, 0x01, 0x88, 0x07, 0xD0, 0x00, 0x00, 0x00])),
+            Step::silent(frame([0xFF, 0x01, 0x79, 0x00, 0x00, 0x00, 0x00, 0x00])),
+            Step::reply(
+                frame([0xFF, 0x01, 0x7D, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                frame([0xFF, 0x7D, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])
+            ),
+            Step::silent(frame([0xFF, 0x01, 0x99, 0x00, 0x00, 0x00, 0x07, 0xD0])),
+            Step::reply(
+                frame([0xFF, 0x01, 0x9B, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                frame([0xFF, 0x9B, 0x00, 0x00, 0x07, 0xD0, 0x00, 0x00])
+            )
+        ];
+
+        let mut mhz = Mhz19::new(ScriptedSerial::new(&script));
+
+        assert_eq!(mhz.co2(), Ok(1200));
+        assert_eq!(mhz.calibrate_zero(), Ok(()));
+        assert_eq!(mhz.calibrate_span(2000), Ok(()));
+        assert_eq!(mhz.auto_calibration(AutoCalibrationState::Disable), Ok(()));
+        assert_eq!(mhz.read_auto_calibration(), Ok(AutoCalibrationState::Disable));
+        assert_eq!(mhz.range(Range::_2000), Ok(()));
+        assert_eq!(mhz.read_range(), Ok(Range::_2000));
+
+        mhz.free().assert_finished();
+    }
+
+    #[test]
+    fn with_range_test() {
+        let script = [Step::silent(frame([0xFF, 0x01, 0x99, 0x00, 0x00, 0x00, 0x07, 0xD0]))];
+
+        let mhz = Mhz19::with_range(ScriptedSerial::new(&script), Range::_2000).unwrap();
+
+        assert_eq!(mhz.full_scale(), 2000);
+
+        mhz.free().assert_finished();
+    }
+
+    #[cfg(feature = "experimental")]
+    #[test]
+    fn alarm_threshold_test() {
+        let script = [Step::silent(frame([0xFF, 0x01, 0x99, 0x00, 0x00, 0x00, 0x07, 0xD0]))];
+
+        let mut mhz = Mhz19::with_range(ScriptedSerial::new(&script), Range::_2000).unwrap();
+
+        // nothing is sent for out of range or unsupported threshold
+        assert_eq!(mhz.set_alarm_threshold(2001), Err(Errors::InvalidArgument));
+        assert_eq!(mhz.set_alarm_threshold(1500), Err(Errors::Unsupported));
+        assert_eq!(mhz.read_alarm_threshold(), Err(Errors::Unsupported));
+
+        mhz.free().assert_finished();
+    }
+
+    #[test]
+    fn validate_capture_test() {
+        let reading = frame([0xFF, 0x86, 0x04, 0xB0, 0x40, 0x00, 0x00, 0x00]);
+        let mut corrupted = reading;
+        corrupted[3] = 0x00;
+
+        let mut capture: Vec<u8> = Vec::new();
+
+        capture.extend_from_slice(&[0x00, 0x13, 0x37]);
+        capture.extend_from_slice(&reading);
+        capture.push(0xFF);
+        capture.extend_from_slice(&reading);
+        capture.extend_from_slice(&corrupted);
+        capture.extend_from_slice(&[0x42, 0x42]);
+        capture.extend_from_slice(&reading);
+        capture.extend_from_slice(&reading[..5]);
+
+        let report = validate_capture(&capture);
+
+        assert_eq!(
+            report,
+            CaptureReport {
+                frames: 3,
+                checksum_failures: 1,
+                // leading garbage, tail of corrupted frame with garbage after it
+                desyncs: 2,
+                skipped_bytes: 3 + 8 + 2,
+                trailing_bytes: 5
+            }
+        );
+
+        let mut parser = FrameParser::new(START_BYTE);
+        let parsed = capture.iter().filter_map(|&byte| parser.push(byte)).count();
+
+        assert_eq!(parsed, report.frames);
+        assert_eq!(validate_capture(&[]), CaptureReport::default());
+    }
+
+    #[test]
+    fn frame_parser_matches_validate_capture_test() {
+        let mut seed: u32 = 0x2545_F491;
+        let mut random = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        };
+
+        for _ in 0..5000 {
+            let mut capture: Vec<u8> = Vec::new();
+
+            while capture.len() < 64 {
+                match random() % 8 {
+                    0 => { capture.extend_from_slice(&frame([0xFF, 0x86, random() as u8, 0xB0, 0x40, 0, 0, 0])); }
+                    1 | 2 => { capture.push(START_BYTE); }
+                    _ => { capture.push(random() as u8); }
+                }
+            }
+
+            let mut parser = FrameParser::new(START_BYTE);
+            let frames: Vec<[u8; 9]> = capture.iter().filter_map(|&byte| parser.push(byte)).collect();
+
+            assert!(frames.iter().all(|frame| frame[1] != START_BYTE));
+            assert_eq!(frames.len(), validate_capture(&capture).frames);
+            assert_eq!(
+                decode_first_frame(&capture).map(|(_, reading)| reading.co2),
+                frames.iter().find(|frame| frame[1] == 0x86).map(|frame| u16::from_be_bytes([frame[2], frame[3]]))
+            );
+        }
+    }
+
+    #[test]
+    fn resync_config_test() {
+        // sensor came back from brown-out with factory settings
+        let script = [
+            Step::silent(frame([0xFF, 0x01, 0x99, 0x00, 0x00, 0x00, 0x07, 0xD0])),
+            Step::reply(
+                frame([0xFF, 0x01, 0x9B, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                frame([0xFF, 0x9B, 0x00, 0x00, 0x13, 0x88, 0x00, 0x00])
+            ),
+            Step::reply(
+                frame([0xFF, 0x01, 0x7D, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                frame([0xFF, 0x7D, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01])
+            )
+        ];
+
+        let mut mhz = Mhz19::with_range(ScriptedSerial::new(&script), Range::_2000).unwrap();
+
+        assert_eq!(mhz.full_scale(), 2000);
+        assert_eq!(mhz.resync_config(), Ok(SensorConfig { measurement_mode: None, ..SensorConfig::default() }));
+        assert_eq!(mhz.full_scale(), 5000);
+
+        mhz.free().assert_finished();
+    }
+
+    fn measure_step() -> Step {
+        Step::reply(
+            frame([0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            frame([0xFF, 0x86, 0x04, 0xB0, 0x40, 0x00, 0x00, 0x00])
+        )
+    }
+
+    #[test]
+    fn faulty_mock_test() {
+        let script = [measure_step(); 4];
+
+        let faults = Faults::default();
+        let mut mhz = Mhz19::new(FaultyMock::new(ScriptedSerial::new(&script[..1]), faults, 1));
+
+        assert_eq!(mhz.co2(), Ok(1200));
+
+        let faults = Faults { hardware_error_percent: 100, ..Faults::default() };
+        let mut mhz = Mhz19::new(FaultyMock::new(ScriptedSerial::new(&script[..1]), faults, 1));
+
+        assert_eq!(mhz.co2(), Err(Errors::ReadHardware));
+
+        // every byte is lost until scripted serial runs dry
+        let faults = Faults { drop_percent: 100, ..Faults::default() };
+        let mut mhz = Mhz19::new(FaultyMock::new(ScriptedSerial::new(&script[..1]), faults, 1));
+
+        assert_eq!(mhz.co2(), Err(Errors::ReadHardware));
+
+        let faults = Faults { corrupt_percent: 100, ..Faults::default() };
+        let mut mhz = Mhz19::new(FaultyMock::new(ScriptedSerial::new(&script[..1]), faults, 1));
+
+        assert!(mhz.co2().is_err());
+
+        let faults = Faults { delay_percent: 100, delay_polls: 3, ..Faults::default() };
+        let mut mhz = Mhz19::new(FaultyMock::new(ScriptedSerial::new(&script[..1]), faults, 1));
+
+        assert_eq!(mhz.co2(), Ok(1200));
+        assert_eq!(mhz.last_read_polls(), 27);
+    }
+
+    #[test]
+    fn faulty_mock_seed_test() {
+        let script = [measure_step(); 8];
+        let faults = Faults { corrupt_percent: 5, drop_percent: 2, ..Faults::default() };
+
+        let run = |seed: u32| {
+            let mut mhz = Mhz19::new(FaultyMock::new(ScriptedSerial::new(&script), faults, seed));
+
+            (0..script.len()).map(|_| mhz.co2()).collect::<Vec<_>>()
+        };
+
+        let results = run(7);
+
+        assert_eq!(results, run(7));
+        assert!(results.iter().any(|result| result.is_ok()));
+        assert!(results.iter().any(|result| result.is_err()));
+    }
+
+    #[test]
+    fn unexpected_command_test() {
+        // sensor answers read concentration to read range
+        let script = [
+            Step::reply(
+                frame([0xFF, 0x01, 0x9B, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                frame([0xFF, 0x86, 0x04, 0xB0, 0x40, 0x00, 0x00, 0x00])
+            )
+        ];
+
+        let mut mhz = Mhz19::new(ScriptedSerial::new(&script));
+
+        assert_eq!(mhz.read_range(), Err(Errors::UnexpectedCommand { expected: 0x9B, got: 0x86 }));
+    }
+
+    #[test]
+    fn default_config_test() {
+        let script = [
+            Step::silent(frame([0xFF, 0x01, 0x99, 0x00, 0x00, 0x00, 0x13, 0x88])),
+            Step::silent(frame([0xFF, 0x01, 0x79, 0xA0, 0x00, 0x00, 0x00, 0x00]))
+        ];
+
+        let mhz = Mhz19::with_config(ScriptedSerial::new(&script), SensorConfig::default()).unwrap();
+
+        mhz.free().assert_finished();
+    }
+
+    #[test]
+    fn frames_equal_ignoring_crc_test() {
+        let script = [Step::silent(frame([0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00]))];
+
+        let mut mhz = Mhz19::new(ScriptedSerial::new(&script));
+
+        // last frame is the sent one when nothing is received
+        assert_eq!(mhz.co2(), Err(Errors::ReadHardware));
+
+        let expected: [u8; 9] = [0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+        assert!(frames_equal_ignoring_crc(mhz.last_frame(), &expected));
+        assert!(!frames_equal_ignoring_crc(mhz.last_frame(), &frame([0xFF, 0x01, 0x87, 0, 0, 0, 0, 0])));
+    }
+
+    #[test]
+    fn no_response_test() {
+        let script = [Step::silent(frame([0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00]))];
+
+        let mut mhz = Mhz19::new(ScriptedSerial::new(&script));
+
+        assert_eq!(mhz.co2(), Err(Errors::ReadHardware));
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected frame at step 0")]
+    fn unexpected_frame_test() {
+        let script = [Step::silent(frame([0xFF, 0x01, 0x87, 0x00, 0x00, 0x00, 0x00, 0x00]))];
+
+        let mut mhz = Mhz19::new(ScriptedSerial::new(&script));
+
+        let _ = mhz.co2();
+    }
+}
@@ -3,7 +3,31 @@ mod common;
 
 #[cfg(test)]
 mod tests {
-    use mhz19_rs::mhz19::{Mhz19, Mhz19Trait};
+    use mhz19_rs::calibration::ZeroCalibration;
+    use mhz19_rs::sampler::PeriodicSampler;
+    use mhz19_rs::history::History;
+    #[cfg(feature = "float")]
+    use mhz19_rs::trend::TrendTracker;
+    #[cfg(feature = "read-buf")]
+    use mhz19_rs::mhz19::BufferedSerial;
+    #[cfg(feature = "async")]
+    use std::time::Duration;
+    use mhz19_rs::mhz19::{
+        decode_log_record,
+        AutoCalibrationState,
+        Errors,
+        FrameTransport,
+        MeasurementMode,
+        Mhz19,
+        Model,
+        Mhz19Trait,
+        Range,
+        ReadFrame,
+        TransportSerial,
+        SensorConfig
+    };
+    #[cfg(feature = "experimental")]
+    use mhz19_rs::mhz19::SensorBaud;
     use crate::common;
 
     #[test]
@@ -15,4 +39,876 @@ mod tests {
 
         assert_eq!(co2, 1200_u16);
     }
+
+    #[test]
+    fn measure_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        let reading = mhz.measure().unwrap();
+
+        assert_eq!(reading.co2, 1200);
+        assert_eq!(reading.temperature, Some(24));
+        assert_eq!(mhz.measure_legacy(), Ok((1200, 24)));
+        assert_eq!(mhz.temperature_only(), Ok(Some(24)));
+    }
+
+    #[test]
+    fn response_field_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        mhz.measure().unwrap();
+
+        assert_eq!(mhz.last_frame()[..5], [0xFF, 0x86, 0x04, 0xB0, 0x40]);
+        assert_eq!(mhz.response_field(4), Some(0x40));
+        assert_eq!(mhz.response_field(8), Some(mhz.last_frame()[8]));
+        assert_eq!(mhz.response_field(9), None);
+    }
+
+    #[test]
+    fn co2_with_frame_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        let (co2, frame) = mhz.co2_with_frame().unwrap();
+
+        assert_eq!(co2, 1200);
+        assert_eq!(frame[..5], [0xFF, 0x86, 0x04, 0xB0, 0x40]);
+        assert_eq!(&frame, mhz.last_frame());
+    }
+
+    #[test]
+    fn co2_timed_test() {
+        let mut mhz = Mhz19::new(common::DummySerial::new());
+
+        assert_eq!(mhz.co2_timed(1_234_567), Ok((1200, 1_234_567)));
+
+        let mut mhz = Mhz19::new(common::DummySerial::broken());
+
+        assert_eq!(mhz.co2_timed(u64::MAX), Err(Errors::ReadHardware));
+    }
+
+    #[test]
+    fn log_record_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        let record = mhz.log_record(0x0102_0304).unwrap();
+
+        assert_eq!(record, [0x01, 0x02, 0x03, 0x04, 0x04, 0xB0, 0x40, 0x00]);
+        assert_eq!(decode_log_record(&record), (0x0102_0304, 1200, 24, 0));
+    }
+
+    #[test]
+    fn floor_test() {
+        let serial = common::DummySerial::warming_up(2);
+        let mut mhz = Mhz19::new(serial);
+
+        assert!(mhz.measure().unwrap().below_floor);
+
+        mhz.set_floor(0);
+
+        assert!(!mhz.measure().unwrap().below_floor);
+
+        mhz.set_floor(400);
+
+        assert!(!mhz.measure().unwrap().below_floor);
+    }
+
+    #[test]
+    fn calibration_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.calibrate_zero(), Ok(()));
+        assert_eq!(mhz.calibrate_span(2000), Ok(()));
+        assert_eq!(mhz.calibration_status(), Err(Errors::Unsupported));
+        assert_eq!(mhz.co2(), Ok(1200));
+    }
+
+    #[test]
+    fn co2_averaged_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.co2_averaged(5), Ok(1200));
+        assert_eq!(mhz.co2_averaged(0), Ok(1200));
+    }
+
+    #[test]
+    fn zero_calibration_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+        let mut calibration = ZeroCalibration::new();
+
+        assert!(!calibration.tick(ZeroCalibration::DWELL_MS));
+        assert_eq!(calibration.commit(&mut mhz), Err(Errors::NotReady));
+
+        calibration.start();
+
+        assert!(!calibration.tick(ZeroCalibration::DWELL_MS - 1));
+        assert_eq!(calibration.commit(&mut mhz), Err(Errors::NotReady));
+        assert!(calibration.tick(1));
+        assert_eq!(calibration.commit(&mut mhz), Ok(()));
+
+        assert!(!calibration.is_ready());
+        assert_eq!(calibration.commit(&mut mhz), Err(Errors::NotReady));
+    }
+
+    #[test]
+    fn periodic_sampler_test() {
+        let serial = common::DummySerial::new();
+        let mhz = Mhz19::new(serial);
+        let mut sampler = PeriodicSampler::new(mhz, common::DummyTimer::default(), 3_u32);
+
+        let mut samples: usize = 0;
+
+        for _ in 0..9 {
+            if let Some(result) = sampler.poll() {
+                assert_eq!(result.map(|reading| reading.co2), Ok(1200));
+                samples += 1;
+            }
+        }
+
+        assert_eq!(samples, 3);
+    }
+
+    #[test]
+    fn history_test() {
+        let serial = common::DummySerial::warming_up(1);
+        let mhz = Mhz19::new(serial);
+        let mut mhz = History::<_, 3>::new(mhz);
+
+        assert!(mhz.history().is_empty());
+
+        for _ in 0..4 {
+            mhz.measure().unwrap();
+        }
+
+        let co2: Vec<u16> = mhz.history().iter().map(|reading| reading.co2).collect();
+
+        // warm-up zero reading is dropped as the oldest one
+        assert_eq!(co2, [1200, 1200, 1200]);
+
+        assert_eq!(mhz.co2(), Ok(1200));
+        assert_eq!(mhz.history().len(), 3);
+
+        mhz.clear();
+
+        assert!(mhz.history().is_empty());
+    }
+
+    #[test]
+    fn history_median_test() {
+        let serial = common::DummySerial::warming_up(2);
+        let mhz = Mhz19::new(serial);
+        let mut mhz = History::<_, 4>::new(mhz);
+
+        assert_eq!(mhz.median(), None);
+
+        mhz.measure().unwrap();
+        mhz.measure().unwrap();
+        mhz.measure().unwrap();
+
+        // readings 0, 0, 1200
+        assert_eq!(mhz.median(), Some(0));
+
+        mhz.measure().unwrap();
+
+        assert_eq!(mhz.median(), Some(600));
+
+        mhz.measure().unwrap();
+
+        // single zero left is a spike
+        assert_eq!(mhz.median(), Some(1200));
+    }
+
+    #[test]
+    fn recover_test() {
+        // head of interrupted frame before real response
+        let serial = common::DummySerial::with_noise(&[0xFF, 0x86, 0x04]);
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.co2(), Err(Errors::Checksum));
+        assert_eq!(mhz.recover(), Ok(()));
+        assert_eq!(mhz.take_last_error(), None);
+        assert_eq!(mhz.co2(), Ok(1200));
+    }
+
+    #[test]
+    fn auto_drain_test() {
+        let serial = common::DummySerial::with_noise(&[0xFF, 0x86, 0x04]);
+        let mut mhz = Mhz19::builder(serial).auto_drain(true).build();
+
+        assert_eq!(mhz.co2(), Ok(1200));
+        assert_eq!(mhz.co2(), Ok(1200));
+    }
+
+    #[test]
+    fn auto_drain_nb_test() {
+        // valid 1000 ppm response left over from timed out transaction
+        let stale: [u8; 9] = [0xFF, 0x86, 0x03, 0xE8, 0x40, 0x00, 0x00, 0x00, 0x4F];
+
+        let mut mhz = Mhz19::new(common::DummySerial::with_noise(&stale));
+
+        assert_eq!(nb::block!(mhz.read_co2()), Ok(1000));
+
+        let serial = common::DummySerial::with_noise(&stale);
+        let mut mhz = Mhz19::builder(serial).auto_drain(true).build();
+
+        assert_eq!(nb::block!(mhz.read_co2()), Ok(1200));
+    }
+
+    #[test]
+    fn measurement_mode_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.read_measurement_mode(), Ok(MeasurementMode::Continuous));
+        assert_eq!(mhz.set_measurement_mode(MeasurementMode::Continuous), Ok(()));
+        assert_eq!(mhz.set_measurement_mode(MeasurementMode::SingleShot), Err(Errors::Unsupported));
+        assert_eq!(mhz.trigger_single_measurement(), Err(Errors::Unsupported));
+
+        let mut delay = common::DummyDelay::default();
+
+        assert_eq!(mhz.co2_single_shot(&mut delay), Err(Errors::Unsupported));
+        assert_eq!(delay.elapsed_ms, 0);
+    }
+
+    #[cfg(feature = "experimental")]
+    #[test]
+    fn set_baud_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.set_baud(SensorBaud::_9600), Ok(()));
+        assert_eq!(mhz.set_baud(SensorBaud::_19200), Err(Errors::Unsupported));
+        assert_eq!(SensorBaud::_19200.bps(), 19200);
+        assert_eq!(mhz.co2(), Ok(1200));
+    }
+
+    #[test]
+    fn info_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        let info = mhz.info().unwrap();
+
+        assert_eq!(info.firmware_version, Some(*b"0443"));
+        assert_eq!(info.range, Some(Range::_5000));
+        assert_eq!(info.abc, Some(AutoCalibrationState::Enable));
+        assert_eq!(info.reading.co2, 1200);
+        assert_eq!(info.reading.temperature, Some(24));
+
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::builder(serial).model(Model::C).build();
+
+        assert_eq!(mhz.firmware_version(), Err(Errors::Unsupported));
+        assert_eq!(mhz.resync_config(), Err(Errors::Unsupported));
+
+        let info = mhz.info().unwrap();
+
+        assert_eq!(info.firmware_version, None);
+        assert_eq!(info.range, None);
+        assert_eq!(info.abc, None);
+        assert_eq!(info.reading.co2, 1200);
+    }
+
+    #[cfg(feature = "experimental")]
+    #[test]
+    fn unsupported_commands_test() {
+        for &model in [Model::B, Model::C].iter() {
+            let serial = common::DummySerial::new();
+            let mut mhz = Mhz19::builder(serial).model(model).build();
+
+            assert_eq!(mhz.operating_hours(), Err(Errors::Unsupported));
+            assert_eq!(mhz.sleep(), Err(Errors::Unsupported));
+            assert_eq!(mhz.wake(), Err(Errors::Unsupported));
+            assert_eq!(mhz.co2(), Ok(1200));
+        }
+    }
+
+    #[test]
+    fn new_checked_test() {
+        let serial = common::DummySerial::new();
+
+        assert!(Mhz19::new_checked(serial).is_ok());
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn co2_percent_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        assert!((mhz.co2_percent().unwrap() - 0.12).abs() < 1e-6);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn trend_test() {
+        let mut trend = TrendTracker::<4>::new();
+
+        assert_eq!(trend.rate_ppm_per_min(), None);
+        assert_eq!(trend.time_to_reach(1000), None);
+
+        trend.push(0, 800);
+
+        assert_eq!(trend.rate_ppm_per_min(), None);
+        assert_eq!(trend.time_to_reach(800), Some(0.0));
+
+        // +20 ppm per minute
+        for minute in 1..5 {
+            trend.push(minute * 60_000, 800 + minute as u16 * 20);
+        }
+
+        assert!((trend.rate_ppm_per_min().unwrap() - 20.0).abs() < 1e-3);
+        assert!((trend.time_to_reach(1000).unwrap() - 6.0).abs() < 1e-3);
+        assert_eq!(trend.time_to_reach(600), None);
+
+        // flat, counter wraps between samples
+        let mut trend = TrendTracker::<4>::new();
+
+        trend.push(u32::MAX - 30_000, 900);
+        trend.push(30_000, 900);
+
+        assert_eq!(trend.rate_ppm_per_min(), Some(0.0));
+        assert_eq!(trend.time_to_reach(1000), None);
+
+        // same timestamp, no rate
+        let mut trend = TrendTracker::<4>::new();
+
+        trend.push(1000, 900);
+        trend.push(1000, 950);
+
+        assert_eq!(trend.rate_ppm_per_min(), None);
+        assert_eq!(trend.time_to_reach(1000), None);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn co2_smoothed_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.co2_smoothed(0.5).unwrap_or(0.0), 1200.0);
+        assert_eq!(mhz.co2_smoothed(0.5).unwrap_or(0.0), 1200.0);
+    }
+
+    #[test]
+    fn co2_blocking_until_valid_test() {
+        let serial = common::DummySerial::warming_up(3);
+        let mut delay = common::DummyDelay::default();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.co2_blocking_until_valid(&mut delay, 10_000), Ok(1200));
+        assert_eq!(delay.elapsed_ms, 3000);
+    }
+
+    #[test]
+    fn ready_gate_test() {
+        let serial = common::DummySerial::warming_up(2);
+        let mut mhz = Mhz19::new(serial);
+        let mut delay = common::DummyDelay::default();
+
+        mhz.set_ready_gate(true);
+
+        assert_eq!(mhz.co2(), Err(Errors::NotReady));
+        assert_eq!(mhz.force_read(), Ok(0));
+        assert_eq!(mhz.wait_until_ready(&mut delay, 10_000), Ok(()));
+        assert_eq!(delay.elapsed_ms, 1000);
+        assert_eq!(mhz.co2(), Ok(1200));
+    }
+
+    #[test]
+    fn ready_gate_lenient_test() {
+        let mut mhz = Mhz19::new(common::DummySerial::warming_up(1));
+        let mut delay = common::DummyDelay::default();
+
+        mhz.set_ready_gate(true);
+
+        assert_eq!(mhz.co2_lenient(), Err(Errors::NotReady));
+        assert_eq!(mhz.wait_until_ready(&mut delay, 10_000), Ok(()));
+        assert_eq!(mhz.co2_lenient(), Ok((1200, true)));
+    }
+
+    #[test]
+    fn ready_gate_quality_test() {
+        let mut mhz = Mhz19::new(common::DummySerial::warming_up(1));
+        let mut delay = common::DummyDelay::default();
+
+        mhz.set_ready_gate(true);
+
+        assert_eq!(mhz.measure_with_quality(), Err(Errors::NotReady));
+        assert_eq!(mhz.wait_until_ready(&mut delay, 10_000), Ok(()));
+        assert_eq!(mhz.measure_with_quality().map(|(reading, _)| reading.co2), Ok(1200));
+    }
+
+    #[test]
+    fn startup_grace_test() {
+        let mut mhz = Mhz19::new(common::DummySerial::with_noise(&[0; 27]));
+
+        assert_eq!(mhz.co2(), Err(Errors::StartByte));
+
+        let mut mhz = Mhz19::new(common::DummySerial::with_noise(&[0; 27]));
+
+        mhz.set_startup_grace(3);
+
+        assert_eq!(mhz.co2(), Err(Errors::NotReady));
+        assert_eq!(mhz.co2(), Ok(1200));
+
+        let mut mhz = Mhz19::new(common::DummySerial::with_noise(&[0; 27]));
+
+        mhz.set_startup_grace(3);
+
+        assert_eq!(mhz.co2_lenient(), Err(Errors::NotReady));
+        assert_eq!(mhz.co2_lenient(), Ok((1200, true)));
+    }
+
+    #[test]
+    fn co2_with_settle_test() {
+        let serial = common::DummySerial::new();
+        let mut delay = common::DummyDelay::default();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.co2_with_settle(&mut delay, 20), Ok(1200));
+        assert_eq!(delay.elapsed_ms, 20);
+
+        let mut mhz = Mhz19::new(common::DummySerial::warming_up(1));
+
+        mhz.set_ready_gate(true);
+
+        assert_eq!(mhz.co2_with_settle(&mut delay, 20), Err(Errors::NotReady));
+        assert_eq!(delay.elapsed_ms, 20);
+    }
+
+    #[test]
+    fn co2_blocking_until_valid_timeout_test() {
+        let serial = common::DummySerial::warming_up(100);
+        let mut delay = common::DummyDelay::default();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.co2_blocking_until_valid(&mut delay, 5000), Err(Errors::Timeout));
+    }
+
+    #[test]
+    fn read_until_test() {
+        let serial = common::DummySerial::warming_up(2);
+        let mut delay = common::DummyDelay::default();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.read_until(|co2| co2 > 1000, &mut delay, 5), Ok(1200));
+        assert_eq!(delay.elapsed_ms, 2000);
+
+        let mut delay = common::DummyDelay::default();
+
+        assert_eq!(mhz.read_until(|co2| co2 < 800, &mut delay, 3), Err(Errors::Timeout));
+        assert_eq!(delay.elapsed_ms, 2000);
+    }
+
+    #[test]
+    fn last_read_polls_test() {
+        let mut mhz = Mhz19::new(common::DummySerial::new());
+
+        assert_eq!(mhz.co2(), Ok(1200));
+        assert_eq!(mhz.last_read_polls(), 0);
+
+        let mut mhz = Mhz19::new(common::DummySerial::stalling(2));
+
+        assert_eq!(mhz.co2(), Ok(1200));
+
+        // byte by byte every read stalls
+        assert_eq!(mhz.last_read_polls(), 18);
+    }
+
+    #[test]
+    fn custom_read_frame_test() {
+        // serial without `Read<u8>`, e.g. packet radio bridge
+        struct Bridge;
+
+        impl embedded_hal::serial::Write<u8> for Bridge {
+            type Error = ();
+
+            fn write(&mut self, _word: u8) -> nb::Result<(), Self::Error> { Ok(()) }
+            fn flush(&mut self) -> nb::Result<(), Self::Error> { Ok(()) }
+        }
+
+        impl ReadFrame for Bridge {
+            fn read_byte(&mut self) -> nb::Result<u8, Errors> {
+                Err(nb::Error::Other(Errors::Read))
+            }
+        }
+
+        let mut mhz = Mhz19::new(Bridge);
+
+        assert_eq!(mhz.co2(), Err(Errors::Read));
+    }
+
+    #[cfg(feature = "read-buf")]
+    #[test]
+    fn buffered_serial_test() {
+        let mut mhz = Mhz19::new(BufferedSerial::new(common::DummySerial::stalling(2)));
+
+        assert_eq!(mhz.co2(), Ok(1200));
+
+        // single read_buf call stalls once
+        assert_eq!(mhz.last_read_polls(), 2);
+        assert_eq!(nb::block!(mhz.read_co2()), Ok(1200));
+
+        let mut mhz = Mhz19::new(BufferedSerial::new(common::DummySerial::broken()));
+
+        assert_eq!(mhz.co2(), Err(Errors::ReadHardware));
+    }
+
+    #[test]
+    fn transport_serial_test() {
+        // packet per frame transport, answers with canned frame
+        struct Packets {
+            sent: Option<[u8; 9]>,
+            reply: [u8; 9],
+            pending: u32,
+            broken: bool
+        }
+
+        impl FrameTransport for Packets {
+            fn send_frame(&mut self, frame: &[u8; 9]) -> nb::Result<(), ()> {
+                if self.pending > 0 {
+                    self.pending -= 1;
+                    return Err(nb::Error::WouldBlock);
+                }
+
+                self.pending = 2;
+                self.sent = Some(*frame);
+                Ok(())
+            }
+
+            fn recv_frame(&mut self) -> nb::Result<[u8; 9], ()> {
+                if self.broken { return Err(nb::Error::Other(())); }
+
+                if self.pending > 0 {
+                    self.pending -= 1;
+                    return Err(nb::Error::WouldBlock);
+                }
+
+                Ok(self.reply)
+            }
+        }
+
+        let reply = [0xFF, 0x86, 0x04, 0xB0, 0x40, 0x00, 0x00, 0x00, 0x86];
+        let packets = Packets { sent: None, reply, pending: 1, broken: false };
+        let mut mhz = Mhz19::new(TransportSerial::new(packets));
+
+        assert_eq!(mhz.co2(), Ok(1200));
+        assert_eq!(mhz.last_read_polls(), 2);
+
+        let mut packets = mhz.free().free();
+
+        assert_eq!(packets.sent, Some([0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79]));
+
+        packets.reply = [0xFF, 0x9B, 0x00, 0x00, 0x13, 0x88, 0x00, 0x00, 0xCA];
+
+        let mut mhz = Mhz19::new(TransportSerial::new(packets));
+
+        assert_eq!(mhz.co2(), Err(Errors::UnexpectedCommand { expected: 0x86, got: 0x9B }));
+
+        let mut packets = mhz.free().free();
+
+        packets.reply[8] = 0x00;
+
+        let mut mhz = Mhz19::new(TransportSerial::new(packets));
+
+        assert_eq!(mhz.co2(), Err(Errors::Checksum));
+
+        let mut packets = mhz.free().free();
+
+        packets.broken = true;
+
+        let mut mhz = Mhz19::new(TransportSerial::new(packets));
+
+        assert_eq!(mhz.co2(), Err(Errors::ReadHardware));
+    }
+
+    #[test]
+    fn read_co2_test() {
+        let mut mhz = Mhz19::new(common::DummySerial::stalling(2));
+        let mut would_block: u32 = 0;
+
+        let co2 = loop {
+            match mhz.read_co2() {
+                Err(nb::Error::WouldBlock) => { would_block += 1; }
+                result => { break result; }
+            }
+        };
+
+        assert_eq!(co2, Ok(1200));
+        assert!(would_block > 0);
+        assert_eq!(nb::block!(mhz.read_co2()), Ok(1200));
+
+        let mut mhz = Mhz19::new(common::DummySerial::broken());
+
+        assert_eq!(mhz.read_co2(), Err(nb::Error::Other(Errors::ReadHardware)));
+        assert_eq!(mhz.take_last_error(), Some(Errors::ReadHardware));
+    }
+
+    #[test]
+    fn read_errors_test() {
+        let mut mhz = Mhz19::new(common::DummySerial::stalling(5));
+
+        mhz.set_read_timeout_polls(Some(3));
+
+        assert_eq!(mhz.co2(), Err(Errors::ReadWouldBlockTimeout));
+        assert!(Errors::ReadWouldBlockTimeout.is_transient());
+
+        mhz.set_read_timeout_polls(None);
+
+        assert_eq!(mhz.co2(), Ok(1200));
+
+        let mut mhz = Mhz19::new(common::DummySerial::broken());
+
+        mhz.set_read_timeout_polls(Some(3));
+
+        assert_eq!(mhz.co2(), Err(Errors::ReadHardware));
+        assert!(!Errors::ReadHardware.is_transient());
+    }
+
+    #[test]
+    fn init_sequence_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.read_range(), Ok(Range::_5000));
+        assert_eq!(mhz.read_auto_calibration(), Ok(AutoCalibrationState::Enable));
+
+        assert_eq!(mhz.range(Range::_2000), Ok(()));
+        assert_eq!(mhz.auto_calibration(AutoCalibrationState::Disable), Ok(()));
+
+        assert_eq!(mhz.read_range(), Ok(Range::_2000));
+        assert_eq!(mhz.read_auto_calibration(), Ok(AutoCalibrationState::Disable));
+        assert_eq!(mhz.warn_if_abc_enabled(), Ok(false));
+
+        assert_eq!(mhz.co2(), Ok(1200));
+    }
+
+    #[test]
+    fn apply_config_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        let mut config = SensorConfig {
+            range: Range::_2000,
+            abc: AutoCalibrationState::Disable,
+            measurement_mode: Some(MeasurementMode::Continuous)
+        };
+
+        assert_eq!(mhz.apply_config(&config), Ok(()));
+        assert_eq!(mhz.read_range(), Ok(Range::_2000));
+        assert_eq!(mhz.read_auto_calibration(), Ok(AutoCalibrationState::Disable));
+
+        // unsupported mode fails before range is changed
+        config.range = Range::_10000;
+        config.measurement_mode = Some(MeasurementMode::SingleShot);
+
+        assert_eq!(mhz.apply_config(&config), Err(Errors::Unsupported));
+        assert_eq!(mhz.read_range(), Ok(Range::_2000));
+    }
+
+    #[test]
+    fn query_test() {
+        let serial = common::DummySerial::short_replies(6);
+        let mut timer = common::DummyTimer::default();
+        let mut mhz = Mhz19::new(serial);
+
+        let frame = mhz.query(0x9B, [0; 5], &mut timer, 3_u32).unwrap();
+
+        assert_eq!(frame[..5], [0xFF, 0x9B, 0x00, 0x00, 0x13]);
+        assert_eq!(frame.len(), 6);
+
+        // full frame ends without waiting for gap
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.query(0x9B, [0; 5], &mut timer, 3_u32).map(|frame| frame.len()), Ok(9));
+
+        // no reply
+        assert_eq!(mhz.query(0x87, [0; 5], &mut timer, 3_u32), Err(Errors::Timeout));
+    }
+
+    #[test]
+    fn free_test() {
+        let mut mhz = Mhz19::builder(common::DummySerial::new()).model(Model::C).build();
+
+        assert_eq!(mhz.range(Range::_2000), Ok(()));
+
+        // serial keeps its state and can be used again
+        let mut mhz = Mhz19::new(mhz.free());
+
+        assert_eq!(mhz.read_range(), Ok(Range::_2000));
+    }
+
+    #[test]
+    fn calibrate_zero_verified_test() {
+        let serial = common::DummySerial::new();
+        let mut delay = common::DummyDelay::default();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.calibrate_zero_verified(&mut delay, 1150, 50), Ok(()));
+        assert_eq!(delay.elapsed_ms, 5000);
+
+        // mock keeps reporting 1200 ppm, far from fresh air
+        assert_eq!(mhz.calibrate_zero_verified(&mut delay, 400, 50), Err(Errors::CalibrationFailed));
+    }
+
+    #[test]
+    fn with_abc_disabled_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        let result = mhz.with_abc_disabled(|mhz| {
+            assert_eq!(mhz.read_auto_calibration(), Ok(AutoCalibrationState::Disable));
+            mhz.calibrate_span(2000)
+        });
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(mhz.read_auto_calibration(), Ok(AutoCalibrationState::Enable));
+
+        // state is restored after error too
+        let result: Result<(), Errors> = mhz.with_abc_disabled(|_| Err(Errors::Timeout));
+
+        assert_eq!(result, Err(Errors::Timeout));
+        assert_eq!(mhz.read_auto_calibration(), Ok(AutoCalibrationState::Enable));
+
+        // disabled ABC stays disabled
+        assert_eq!(mhz.auto_calibration(AutoCalibrationState::Disable), Ok(()));
+        assert_eq!(mhz.with_abc_disabled(|mhz| mhz.co2()), Ok(1200));
+        assert_eq!(mhz.read_auto_calibration(), Ok(AutoCalibrationState::Disable));
+    }
+
+    #[test]
+    fn with_config_test() {
+        let config = SensorConfig { range: Range::_2000, ..SensorConfig::default() };
+        let mut mhz = Mhz19::with_config(common::DummySerial::new(), config).unwrap();
+
+        assert_eq!(mhz.read_range(), Ok(Range::_2000));
+        assert_eq!(mhz.read_auto_calibration(), Ok(AutoCalibrationState::Enable));
+        assert_eq!(SensorConfig::default().measurement_mode, Some(MeasurementMode::Continuous));
+    }
+
+    #[test]
+    fn ensure_range_test() {
+        // mock starts with factory range, stale for application
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.ensure_range(Range::_2000), Ok(true));
+        assert_eq!(mhz.read_range(), Ok(Range::_2000));
+        assert_eq!(mhz.ensure_range(Range::_2000), Ok(false));
+
+        mhz.set_model(Model::C);
+
+        assert_eq!(mhz.ensure_range(Range::_2000), Err(Errors::Unsupported));
+    }
+
+    #[cfg(feature = "sensor")]
+    #[test]
+    fn sensor_test() {
+        use mhz19_rs::sensor::Sensor;
+
+        fn read_co2<SensorType>(sensor: &mut SensorType) -> Option<u16>
+            where
+                SensorType: Sensor<Measurement = mhz19_rs::mhz19::Reading>
+        {
+            sensor.read().ok().map(|reading| reading.co2)
+        }
+
+        let mut mhz = Mhz19::new(common::DummySerial::new());
+
+        assert_eq!(read_co2(&mut mhz), Some(1200));
+    }
+
+    #[test]
+    fn factory_reset_test() {
+        let serial = common::DummySerial::new();
+        let mut delay = common::DummyDelay::default();
+        let mut mhz = Mhz19::new(serial);
+
+        assert_eq!(mhz.range(Range::_2000), Ok(()));
+        assert_eq!(mhz.auto_calibration(AutoCalibrationState::Disable), Ok(()));
+
+        assert_eq!(mhz.factory_reset(&mut delay), Ok(()));
+        assert_eq!(delay.elapsed_ms, 5000);
+        assert_eq!(mhz.full_scale(), 5000);
+
+        assert_eq!(mhz.read_range(), Ok(Range::_5000));
+        assert_eq!(mhz.read_auto_calibration(), Ok(AutoCalibrationState::Enable));
+
+        mhz.set_model(Model::C);
+
+        assert_eq!(mhz.factory_reset(&mut delay), Err(Errors::Unsupported));
+    }
+
+    #[test]
+    fn settle_delay_test() {
+        let serial = common::DummySerial::new();
+        let mut delay = common::DummyDelay::default();
+
+        {
+            let mut mhz = Mhz19::builder(serial).settle_delay(&mut delay, 100).build();
+
+            assert_eq!(mhz.range(Range::_2000), Ok(()));
+            assert_eq!(mhz.auto_calibration(AutoCalibrationState::Disable), Ok(()));
+            assert_eq!(mhz.co2(), Ok(1200));
+        }
+
+        assert_eq!(delay.elapsed_ms, 200);
+    }
+
+    #[test]
+    fn turnaround_delay_test() {
+        let serial = common::DummySerial::new();
+        let mut delay = common::DummyDelay::default();
+
+        {
+            let mut mhz = Mhz19::builder(serial).delay(&mut delay).turnaround_ms(5).build();
+
+            assert_eq!(mhz.co2(), Ok(1200));
+            assert_eq!(mhz.read_range(), Ok(Range::_5000));
+            assert_eq!(mhz.range(Range::_2000), Ok(()));
+        }
+
+        assert_eq!(delay.elapsed_ms, 10);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn stream_test() {
+        let mut timer = common::DummyAsyncTimer::default();
+        let mut mhz = Mhz19::new(common::DummySerial::stalling(3));
+
+        {
+            let mut stream = mhz.stream(&mut timer, Duration::from_secs(5));
+
+            for _ in 0..3 {
+                let co2 = common::block_on(stream.next()).map(|result| result.map(|reading| reading.co2));
+
+                assert_eq!(co2, Some(Ok(1200)));
+            }
+        }
+
+        assert_eq!(timer.requested, [Duration::from_secs(5); 3]);
+
+        let mut mhz = Mhz19::new(common::DummySerial::broken());
+        let mut stream = mhz.stream(&mut timer, Duration::from_secs(5));
+        let co2 = common::block_on(stream.next()).map(|result| result.map(|reading| reading.co2));
+
+        assert_eq!(co2, Some(Err(Errors::ReadHardware)));
+    }
+
+    #[test]
+    fn warn_if_abc_enabled_test() {
+        let serial = common::DummySerial::new();
+        let mut mhz = Mhz19::new(serial);
+
+        assert!(mhz.warn_if_abc_enabled().unwrap_or(false));
+    }
 }